@@ -0,0 +1 @@
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS stream_max_length BIGINT NULL CHECK (stream_max_length > 0);
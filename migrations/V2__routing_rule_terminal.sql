@@ -0,0 +1 @@
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS terminal BOOLEAN NOT NULL DEFAULT TRUE;
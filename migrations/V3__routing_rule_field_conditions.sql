@@ -0,0 +1 @@
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS field_conditions JSONB NOT NULL DEFAULT '{}'::jsonb;
@@ -0,0 +1 @@
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS payload_conditions JSONB NOT NULL DEFAULT '[]'::jsonb;
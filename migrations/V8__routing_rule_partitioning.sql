@@ -0,0 +1,2 @@
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS partition_key JSONB;
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS partition_metadata_field TEXT;
@@ -0,0 +1,505 @@
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    gateway::gateway::{
+        EventResult, EventStatus, Explanation, GateWay, GatewayError, SchemaValidationFailure,
+        TopicOutcome,
+    },
+    model::event::Event,
+    model::routing::{TopicRoutingRule, TopicValidationConfig},
+    store::deduplication::{Claim, DeduplicationStore},
+};
+
+/// Transport metadata key of the authenticated caller, which scopes event ids
+/// and idempotency keys.
+const CALLER: &str = "jwt_sub";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeduplicationBackend {
+    InMemory,
+    /// Uses the `idempotency_keys` table of the `postgres` database.
+    Postgres,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeduplicationConfig {
+    pub backend: DeduplicationBackend,
+    /// How long the outcome of an accepted event is replayed to duplicates.
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// How long an event being handled blocks its duplicates, in case the
+    /// gateway handling it stops before completing.
+    #[serde(default = "default_in_flight_secs")]
+    pub in_flight_secs: u64,
+}
+
+fn default_ttl_secs() -> u64 {
+    86400 // 24 hours
+}
+
+fn default_in_flight_secs() -> u64 {
+    60
+}
+
+/// Replays the outcome of an event accepted within the deduplication window
+/// instead of publishing it again. Events are keyed by their
+/// `Idempotency-Key` header when present, otherwise by `Event.id`, scoped to
+/// the caller's JWT subject.
+pub struct DeduplicatingEventGateway<T: GateWay> {
+    gateway: T,
+    store: Arc<dyn DeduplicationStore>,
+    ttl: Duration,
+    in_flight: Duration,
+}
+
+/// Releases a claimed key unless its outcome was recorded, including when
+/// the request is dropped midway.
+struct ClaimGuard {
+    store: Arc<dyn DeduplicationStore>,
+    key: Option<String>,
+}
+
+impl ClaimGuard {
+    fn disarm(&mut self) {
+        self.key = None;
+    }
+}
+
+impl Drop for ClaimGuard {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let store = Arc::clone(&self.store);
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                if let Err(e) = store.release(&key).await {
+                    warn!("Failed to release idempotency key {key}: {e}");
+                }
+            });
+        }
+    }
+}
+
+impl<T> DeduplicatingEventGateway<T>
+where
+    T: GateWay,
+{
+    pub fn new(
+        gateway: T,
+        store: Arc<dyn DeduplicationStore>,
+        config: &DeduplicationConfig,
+    ) -> Self {
+        DeduplicatingEventGateway {
+            gateway,
+            store,
+            ttl: Duration::from_secs(config.ttl_secs),
+            in_flight: Duration::from_secs(config.in_flight_secs),
+        }
+    }
+
+    fn key(event: &Event, idempotency_key: Option<&str>) -> String {
+        let caller = event
+            .transport_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(CALLER))
+            .map(String::as_str)
+            .unwrap_or_default();
+        // The caller's length keeps a ':' in the subject from making two
+        // callers' keys collide.
+        match idempotency_key {
+            Some(key) => format!("key:{}:{caller}:{key}", caller.len()),
+            None => format!("event:{}:{caller}:{}", caller.len(), event.id),
+        }
+    }
+}
+
+/// Outcome of a duplicate of `event`, or the error of a rejected event; the
+/// schema validation errors of the original response are not replayed.
+fn replay(event: &Event, outcomes: Vec<TopicOutcome>) -> Result<Vec<TopicOutcome>, GatewayError> {
+    let replayed = EventResult::new(event.id, outcomes);
+    match replayed.status {
+        EventStatus::Published | EventStatus::DeadLettered => Ok(replayed.topics),
+        EventStatus::SchemaInvalid => Err(GatewayError::SchemaInvalid(SchemaValidationFailure {
+            event_id: event.id,
+            topic: replayed
+                .topics
+                .into_iter()
+                .find(|topic| topic.status == EventStatus::SchemaInvalid)
+                .map(|topic| topic.topic)
+                .unwrap_or_default(),
+            schemas: Vec::new(),
+        })),
+        EventStatus::TopicRenderFailed => Err(GatewayError::TopicRenderFailed(format!(
+            "event {} was dead-lettered before",
+            event.id
+        ))),
+        _ => Err(GatewayError::NoTopicToRoute(format!(
+            "No topic to route event: {:?}",
+            event.id
+        ))),
+    }
+}
+
+#[async_trait]
+impl<T> GateWay for DeduplicatingEventGateway<T>
+where
+    T: GateWay,
+{
+    async fn handle(
+        &self,
+        event: &Event,
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<TopicOutcome>, GatewayError> {
+        let key = Self::key(event, idempotency_key);
+        match self.store.claim(&key, self.in_flight).await? {
+            Claim::Claimed => {}
+            Claim::InFlight => return Err(GatewayError::DuplicateInFlight(key)),
+            Claim::Completed(outcomes) => {
+                debug!("Replaying outcome of duplicate event {} ({key})", event.id);
+                return replay(event, outcomes);
+            }
+        }
+
+        let mut guard = ClaimGuard {
+            store: Arc::clone(&self.store),
+            key: Some(key.clone()),
+        };
+        let result = self.gateway.handle(event, idempotency_key).await;
+        // Accepted and dead-lettered events are remembered; other rejected
+        // ones published nothing and may succeed on retry.
+        let outcomes = match &result {
+            Ok(outcomes) | Err(GatewayError::DeadLetterRejected { outcomes, .. }) => outcomes,
+            Err(_) => return result,
+        };
+        match self.store.complete(&key, outcomes, self.ttl).await {
+            Ok(()) => guard.disarm(),
+            Err(e) => warn!("Failed to record outcome of event {}: {e}", event.id),
+        }
+        result
+    }
+
+    async fn publish_batch(
+        &self,
+        events: &[Event],
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<EventResult>, GatewayError> {
+        let mut results: Vec<Option<EventResult>> = vec![None; events.len()];
+        let mut claimed = Vec::new();
+        for (index, event) in events.iter().enumerate() {
+            // A batch's Idempotency-Key covers each event by its position.
+            let event_key = idempotency_key.map(|key| format!("{key}:{index}"));
+            let key = Self::key(event, event_key.as_deref());
+            match self.store.claim(&key, self.in_flight).await? {
+                Claim::Claimed => {
+                    let guard = ClaimGuard {
+                        store: Arc::clone(&self.store),
+                        key: Some(key.clone()),
+                    };
+                    claimed.push((index, key, guard));
+                }
+                Claim::InFlight => {
+                    results[index] = Some(EventResult {
+                        id: event.id,
+                        status: EventStatus::DuplicateInFlight,
+                        topics: Vec::new(),
+                    });
+                }
+                Claim::Completed(outcomes) => {
+                    debug!("Replaying outcome of duplicate event {} ({key})", event.id);
+                    results[index] = Some(EventResult::new(event.id, outcomes));
+                }
+            }
+        }
+
+        let batch: Vec<Event> = claimed
+            .iter()
+            .map(|(index, _, _)| events[*index].clone())
+            .collect();
+        let published = self.gateway.publish_batch(&batch, idempotency_key).await?;
+        for ((index, key, mut guard), result) in claimed.into_iter().zip(published) {
+            // Events are remembered like in `handle`; the dead-letter copy of
+            // a rejected event is left out so that it is replayed as rejected.
+            let outcomes: Vec<TopicOutcome> = match result.status {
+                EventStatus::Published | EventStatus::DeadLettered => result.topics.clone(),
+                _ if result
+                    .topics
+                    .iter()
+                    .any(|topic| topic.status == EventStatus::DeadLettered) =>
+                {
+                    result
+                        .topics
+                        .iter()
+                        .filter(|topic| topic.status != EventStatus::DeadLettered)
+                        .cloned()
+                        .collect()
+                }
+                _ => {
+                    results[index] = Some(result);
+                    continue;
+                }
+            };
+            match self.store.complete(&key, &outcomes, self.ttl).await {
+                Ok(()) => guard.disarm(),
+                Err(e) => warn!("Failed to record outcome of event {}: {e}", result.id),
+            }
+            results[index] = Some(result);
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    async fn explain(&self, event: &Event) -> Result<Explanation, GatewayError> {
+        self.gateway.explain(event).await
+    }
+
+    fn disconnected_publishers(&self) -> Vec<String> {
+        self.gateway.disconnected_publishers()
+    }
+
+    async fn add_topic_validation(&self, v: &TopicValidationConfig) -> Result<(), GatewayError> {
+        self.gateway.add_topic_validation(v).await
+    }
+
+    async fn delete_topic_validation(&self, id: &Uuid) -> Result<(), GatewayError> {
+        self.gateway.delete_topic_validation(id).await
+    }
+
+    async fn add_routing_rule(&self, rule: &TopicRoutingRule) -> Result<(), GatewayError> {
+        self.gateway.add_routing_rule(rule).await
+    }
+
+    async fn get_routing_rules(&self) -> Result<Vec<TopicRoutingRule>, GatewayError> {
+        self.gateway.get_routing_rules().await
+    }
+
+    async fn delete_routing_rule(&self, id: &Uuid) -> Result<(), GatewayError> {
+        self.gateway.delete_routing_rule(id).await
+    }
+
+    async fn get_topic_validations(
+        &self,
+    ) -> Result<HashMap<String, Vec<TopicValidationConfig>>, GatewayError> {
+        self.gateway.get_topic_validations().await
+    }
+
+    async fn update_routing_rule(
+        &self,
+        id: Uuid,
+        rule: &TopicRoutingRule,
+    ) -> Result<(), GatewayError> {
+        self.gateway.update_routing_rule(id, rule).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::gateway::gateway::{DeadLetterConfig, DeadLetterPolicy, EventGateway};
+    use crate::model::event::Data;
+    use crate::model::expressions::Condition;
+    use crate::model::topic::TopicTemplate;
+    use crate::publisher::publisher::{PublishContext, Publisher, PublisherError};
+    use crate::store::deduplication::InMemoryDeduplicationStore;
+    use crate::store::storage::{InMemoryStorage, Storage};
+
+    #[derive(Clone, Default)]
+    struct CountingPublisher(Arc<Mutex<usize>>);
+
+    #[async_trait]
+    impl Publisher<Event> for CountingPublisher {
+        async fn publish_one(
+            &self,
+            _topic: &str,
+            _payload: Event,
+            _context: PublishContext,
+        ) -> Result<(), PublisherError> {
+            *self.0.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    async fn gateway() -> (DeduplicatingEventGateway<EventGateway>, CountingPublisher) {
+        let storage = InMemoryStorage::new();
+        storage
+            .add_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders").unwrap(),
+                event_type_condition: Condition::ANY,
+                event_version_condition: None,
+                field_conditions: Default::default(),
+                payload_conditions: Vec::new(),
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+            })
+            .await
+            .unwrap();
+        let publisher = CountingPublisher::default();
+        let gateway = DeduplicatingEventGateway::new(
+            EventGateway::new(Box::new(publisher.clone()), Box::new(storage)),
+            Arc::new(InMemoryDeduplicationStore::new()),
+            &DeduplicationConfig {
+                backend: DeduplicationBackend::InMemory,
+                ttl_secs: 60,
+                in_flight_secs: 60,
+            },
+        );
+        (gateway, publisher)
+    }
+
+    fn event() -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            metadata: HashMap::new(),
+            transport_metadata: None,
+            data_type: None,
+            data: Data::String("data".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_duplicate_events() {
+        let (gateway, publisher) = gateway().await;
+        let event = event();
+        let retry = || Event {
+            id: Uuid::new_v4(),
+            ..event.clone()
+        };
+        let from = |caller: &str| Event {
+            transport_metadata: Some(HashMap::from([(CALLER.to_string(), caller.to_string())])),
+            ..retry()
+        };
+
+        let outcomes = gateway.handle(&event, None).await.unwrap();
+        assert_eq!(outcomes[0].status, EventStatus::Published);
+        assert_eq!(gateway.handle(&event, None).await.unwrap(), outcomes);
+        assert_eq!(*publisher.0.lock().unwrap(), 1);
+
+        gateway.handle(&retry(), Some("retry-1")).await.unwrap();
+        gateway.handle(&retry(), Some("retry-1")).await.unwrap();
+        gateway.handle(&retry(), Some("retry-2")).await.unwrap();
+        assert_eq!(*publisher.0.lock().unwrap(), 3);
+
+        gateway
+            .handle(&from("alice"), Some("retry-1"))
+            .await
+            .unwrap();
+        gateway
+            .handle(&from("alice"), Some("retry-1"))
+            .await
+            .unwrap();
+        gateway.handle(&from("bob"), Some("retry-1")).await.unwrap();
+        assert_eq!(*publisher.0.lock().unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn scopes_event_ids_to_the_caller() {
+        let (gateway, publisher) = gateway().await;
+        let event = event();
+        let from = |caller: &str| Event {
+            transport_metadata: Some(HashMap::from([(CALLER.to_string(), caller.to_string())])),
+            ..event.clone()
+        };
+
+        gateway.handle(&from("alice"), None).await.unwrap();
+        gateway.handle(&from("alice"), None).await.unwrap();
+        assert_eq!(*publisher.0.lock().unwrap(), 1);
+
+        gateway.handle(&from("bob"), None).await.unwrap();
+        gateway.handle(&from("bob"), None).await.unwrap();
+        assert_eq!(*publisher.0.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn replays_rejections_of_dead_lettered_events() {
+        let publisher = CountingPublisher::default();
+        let gateway = DeduplicatingEventGateway::new(
+            EventGateway::new(
+                Box::new(publisher.clone()),
+                Box::new(InMemoryStorage::new()),
+            )
+            .with_dead_letter(DeadLetterConfig {
+                topic: "events.dlq".to_string(),
+                policy: DeadLetterPolicy::Reject,
+            })
+            .unwrap(),
+            Arc::new(InMemoryDeduplicationStore::new()),
+            &DeduplicationConfig {
+                backend: DeduplicationBackend::InMemory,
+                ttl_secs: 60,
+                in_flight_secs: 60,
+            },
+        );
+        let rejected = event();
+
+        assert!(matches!(
+            gateway.handle(&rejected, None).await,
+            Err(GatewayError::DeadLetterRejected { .. })
+        ));
+        assert!(matches!(
+            gateway.handle(&rejected, None).await,
+            Err(GatewayError::NoTopicToRoute(_))
+        ));
+        assert_eq!(*publisher.0.lock().unwrap(), 1);
+
+        let events = [rejected, event()];
+        let results = gateway.publish_batch(&events, None).await.unwrap();
+        assert!(results
+            .iter()
+            .all(|result| result.status == EventStatus::NoRoute));
+        let replayed = gateway.publish_batch(&events, None).await.unwrap();
+        assert_eq!(replayed[1].status, EventStatus::NoRoute);
+        assert_eq!(*publisher.0.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn replays_duplicate_events_in_batches() {
+        let (gateway, publisher) = gateway().await;
+        let events = [event(), event()];
+
+        let results = gateway.publish_batch(&events, None).await.unwrap();
+        assert!(results
+            .iter()
+            .all(|result| result.status == EventStatus::Published));
+        let retried = [events[1].clone(), event()];
+        let replayed = gateway.publish_batch(&retried, None).await.unwrap();
+        assert_eq!(replayed[0], results[1]);
+        assert_eq!(replayed[1].status, EventStatus::Published);
+        assert_eq!(*publisher.0.lock().unwrap(), 3);
+
+        let keyed = [event(), event()];
+        let results = gateway
+            .publish_batch(&keyed, Some("batch-1"))
+            .await
+            .unwrap();
+        let retried = [event(), event()];
+        let replayed = gateway
+            .publish_batch(&retried, Some("batch-1"))
+            .await
+            .unwrap();
+        assert_eq!(replayed[0].id, retried[0].id);
+        assert_eq!(replayed[0].topics, results[0].topics);
+        assert_eq!(*publisher.0.lock().unwrap(), 5);
+    }
+}
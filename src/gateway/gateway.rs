@@ -1,23 +1,49 @@
 use async_trait::async_trait;
-use log::debug;
-use std::{collections::HashMap, sync::Arc};
+use futures::future::join_all;
+use log::{debug, error, warn};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use crate::{
     model::{
         event::{Data, Event},
-        routing::{DataSchema, TopicRoutingRule, TopicValidationConfig},
+        routing::{DataSchema, TopicRoutingRule, TopicValidationConfig, ValidationError},
+        topic::{Topic, TopicNaming, TopicValidationError},
+    },
+    publisher::publisher::{PublishContext, PublishRequest, Publisher, PublisherError},
+    router::{
+        compiled::CompiledRoutings,
+        router::{failed_conditions, TopicRouter, TopicRoutings},
     },
-    publisher::publisher::{PublishContext, Publisher, PublisherError},
-    router::router::{TopicRouter, TopicRoutings},
     store::storage::{Storage, StorageError},
 };
 
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use uuid::Uuid;
 
 #[async_trait]
 pub trait GateWay: Send + Sync {
-    async fn handle(&self, event: &Event) -> Result<(), GatewayError>;
+    /// `idempotency_key` is the request's `Idempotency-Key` header, which
+    /// identifies retries without being published with the event.
+    async fn handle(
+        &self,
+        event: &Event,
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<TopicOutcome>, GatewayError>;
+    async fn publish_batch(
+        &self,
+        events: &[Event],
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<EventResult>, GatewayError>;
+    /// Routes and validates `event` like [`GateWay::handle`] without
+    /// publishing it.
+    async fn explain(&self, event: &Event) -> Result<Explanation, GatewayError>;
+    /// Names of publishers that lost their broker connection, `default` being
+    /// the default publisher.
+    fn disconnected_publishers(&self) -> Vec<String>;
 
     async fn add_routing_rule(&self, rule: &TopicRoutingRule) -> Result<(), GatewayError>;
     async fn update_routing_rule(
@@ -37,27 +63,748 @@ pub trait GateWay: Send + Sync {
 
 #[derive(Debug)]
 pub enum GatewayError {
-    SchemaInvalid(String),
+    SchemaInvalid(SchemaValidationFailure),
     NoTopicToRoute(String),
+    TopicRenderFailed(String),
+    InvalidRule(String),
+    /// Another request with the same idempotency key is still being handled.
+    DuplicateInFlight(String),
+    /// `error` rejected the event, which the dead-letter topic published
+    /// under [`DeadLetterPolicy::Reject`]. `outcomes` are those of its
+    /// destinations.
+    DeadLetterRejected {
+        error: Box<GatewayError>,
+        outcomes: Vec<TopicOutcome>,
+    },
     InternalError(String),
 }
 
 impl std::fmt::Display for GatewayError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            GatewayError::SchemaInvalid(msg) => write!(f, "Schema validation failed: {msg}"),
+            GatewayError::SchemaInvalid(failure) => {
+                write!(f, "Schema validation failed: {failure}")
+            }
             GatewayError::NoTopicToRoute(msg) => write!(f, "No topic to route: {msg}"),
+            GatewayError::TopicRenderFailed(msg) => write!(f, "Topic rendering failed: {msg}"),
+            GatewayError::InvalidRule(msg) => write!(f, "Invalid routing rule: {msg}"),
+            GatewayError::DuplicateInFlight(key) => {
+                write!(
+                    f,
+                    "Event with idempotency key {key} is already being handled"
+                )
+            }
+            GatewayError::DeadLetterRejected { error, .. } => {
+                write!(f, "{error}, published to the dead-letter topic")
+            }
             GatewayError::InternalError(msg) => write!(f, "Internal error: {msg}"),
         }
     }
 }
 
+/// Every schema an event failed for one destination topic.
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaValidationFailure {
+    pub event_id: Uuid,
+    pub topic: String,
+    pub schemas: Vec<SchemaErrors>,
+}
+
+/// Validation errors reported by one schema.
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaErrors {
+    pub name: String,
+    pub errors: Vec<ValidationError>,
+}
+
+impl std::fmt::Display for SchemaValidationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Event {} failed schema validation", self.event_id)?;
+        for schema in &self.schemas {
+            let details = schema
+                .errors
+                .iter()
+                .map(|e| {
+                    format!(
+                        "Field '{}': {} (at schema path: {})",
+                        e.instance_path, e.message, e.schema_path
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            write!(f, " for '{}': {details}", schema.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of an event, or of one of its destination topics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    Published,
+    DeadLettered,
+    SchemaInvalid,
+    NoRoute,
+    TopicRenderFailed,
+    /// Another request with the same idempotency key is still being handled.
+    DuplicateInFlight,
+    InternalError,
+}
+
+/// Outcome of publishing an event to one of its matched topics. `topic` is the
+/// rule's template when it could not be rendered.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TopicOutcome {
+    pub topic: String,
+    pub status: EventStatus,
+}
+
+/// Outcome of a single event submitted through [`GateWay::publish_batch`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EventResult {
+    pub id: Uuid,
+    pub status: EventStatus,
+    pub topics: Vec<TopicOutcome>,
+}
+
+impl EventResult {
+    /// Result of an event with the aggregate status of its topic outcomes.
+    pub fn new(id: Uuid, topics: Vec<TopicOutcome>) -> Self {
+        EventResult {
+            id,
+            status: aggregate_status(&topics),
+            topics,
+        }
+    }
+}
+
+/// Dry run of an event through routing and schema validation.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Explanation {
+    pub event_id: Uuid,
+    /// Status the event would get from [`GateWay::publish_batch`].
+    pub status: EventStatus,
+    /// Rules in routing order, up to the first matching terminal rule.
+    pub rules: Vec<RuleExplanation>,
+    pub destinations: Vec<DestinationExplanation>,
+    /// Dead-letter topic the event would be copied to.
+    pub dead_letter: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleExplanation {
+    pub id: Uuid,
+    pub order: i32,
+    pub topic: String,
+    pub matched: bool,
+    pub failed_conditions: Vec<String>,
+}
+
+/// A matched rule's destination. `topic` is the rule's template when it could
+/// not be rendered, with the reason in `error`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestinationExplanation {
+    pub rule_id: Uuid,
+    pub topic: String,
+    pub publisher: Option<String>,
+    pub status: EventStatus,
+    pub error: Option<String>,
+    pub schemas: Vec<SchemaExplanation>,
+}
+
+/// A schema selected by the event's type and version, and its result.
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaExplanation {
+    pub name: String,
+    pub status: SchemaStatus,
+    pub errors: Vec<ValidationError>,
+}
+
+/// Schemas are skipped for string and binary event data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaStatus {
+    Valid,
+    Invalid,
+    Skipped,
+}
+
+/// Result of routing, validating and publishing an event to one destination,
+/// or to the dead-letter topic.
+struct Delivery {
+    topic: String,
+    result: Result<(), GatewayError>,
+    dead_letter: bool,
+}
+
+impl From<&Delivery> for TopicOutcome {
+    fn from(delivery: &Delivery) -> Self {
+        let status = match &delivery.result {
+            Ok(()) if delivery.dead_letter => EventStatus::DeadLettered,
+            Ok(()) => EventStatus::Published,
+            Err(GatewayError::SchemaInvalid(_)) => EventStatus::SchemaInvalid,
+            Err(GatewayError::NoTopicToRoute(_)) => EventStatus::NoRoute,
+            Err(GatewayError::TopicRenderFailed(_)) => EventStatus::TopicRenderFailed,
+            Err(GatewayError::DuplicateInFlight(_)) => EventStatus::DuplicateInFlight,
+            Err(
+                GatewayError::InvalidRule(_)
+                | GatewayError::DeadLetterRejected { .. }
+                | GatewayError::InternalError(_),
+            ) => EventStatus::InternalError,
+        };
+        TopicOutcome {
+            topic: delivery.topic.clone(),
+            status,
+        }
+    }
+}
+
+/// Summarizes the per-topic outcomes of an event with the same precedence as
+/// [`EventGateway::settle`].
+fn aggregate_status(outcomes: &[TopicOutcome]) -> EventStatus {
+    [
+        EventStatus::Published,
+        EventStatus::DeadLettered,
+        EventStatus::InternalError,
+        EventStatus::TopicRenderFailed,
+        EventStatus::SchemaInvalid,
+    ]
+    .into_iter()
+    .find(|status| outcomes.iter().any(|outcome| outcome.status == *status))
+    .unwrap_or(EventStatus::NoRoute)
+}
+
+/// Whether an event accepted by the dead-letter topic is reported to the
+/// producer as rejected or as accepted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeadLetterPolicy {
+    #[default]
+    Reject,
+    Accept,
+}
+
+/// Destination for events that matched no routing rule or could not be
+/// published to any matched topic because of schema validation or topic
+/// rendering failures.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeadLetterConfig {
+    pub topic: String,
+    #[serde(default)]
+    pub policy: DeadLetterPolicy,
+}
+
+struct DeadLetter {
+    topic: Topic,
+    policy: DeadLetterPolicy,
+}
+
+/// Copy of `event` for the dead-letter topic, with the failure attached to its
+/// transport metadata. `topics` are the destinations the event could not be
+/// published to, empty when it matched no rule.
+fn dead_letter_event(
+    event: &Event,
+    failure: Option<&SchemaValidationFailure>,
+    topics: &[&str],
+) -> Event {
+    let mut event = event.clone();
+    let metadata = event.transport_metadata.get_or_insert_with(HashMap::new);
+    match failure {
+        None if !topics.is_empty() => {
+            metadata.insert("dead_letter_reason".into(), "topic_render_failed".into());
+            metadata.insert("dead_letter_topics".into(), topics.join(","));
+        }
+        Some(failure) => {
+            metadata.insert("dead_letter_reason".into(), "schema_invalid".into());
+            let names: Vec<&str> = failure
+                .schemas
+                .iter()
+                .map(|schema| schema.name.as_str())
+                .collect();
+            metadata.insert("dead_letter_schema".into(), names.join(","));
+            metadata.insert(
+                "dead_letter_errors".into(),
+                serde_json::to_string(&failure.schemas).unwrap_or_default(),
+            );
+            metadata.insert("dead_letter_topics".into(), topics.join(","));
+        }
+        None => {
+            metadata.insert("dead_letter_reason".into(), "no_route".into());
+        }
+    }
+    event
+}
+
+/// Publisher request an outcome belongs to: a destination of the event, or its
+/// dead-letter copy.
+enum Target {
+    Destination(usize),
+    DeadLetter,
+}
+
 pub struct EventGateway {
+    /// Publisher for rules without a `publisher` and for dead letters.
     publisher: Box<dyn Publisher<Event>>,
+    publishers: HashMap<String, Box<dyn Publisher<Event>>>,
     store: Arc<Box<dyn Storage>>,
+    dead_letter: Option<DeadLetter>,
+    /// Compiled rules and the storage revision they were built from.
+    routings: RwLock<Option<(u64, Arc<dyn TopicRouter + Send + Sync>)>>,
 }
 
 impl EventGateway {
+    /// Returns the compiled routing index, rebuilding it only when the
+    /// storage reports a new rules revision. Storages without revisions are
+    /// scanned linearly, as compiling per request would cost more.
+    async fn routings(&self) -> Result<Arc<dyn TopicRouter + Send + Sync>, GatewayError> {
+        let revision = self.store.rules_revision();
+        if let Some(revision) = revision {
+            if let Some((cached, routings)) = self.routings.read().unwrap().as_ref() {
+                if *cached == revision {
+                    return Ok(Arc::clone(routings));
+                }
+            }
+        }
+
+        let rules = self
+            .store
+            .get_all_rules()
+            .await
+            .map_err(GatewayError::from)?;
+        let Some(revision) = revision else {
+            return Ok(Arc::new(TopicRoutings { rules }));
+        };
+        let routings: Arc<dyn TopicRouter + Send + Sync> = Arc::new(CompiledRoutings::new(rules));
+        debug!("compiled routing rules for revision {revision}");
+        *self.routings.write().unwrap() = Some((revision, Arc::clone(&routings)));
+        Ok(routings)
+    }
+
+    /// Routes every event to all of its matching rules, validates it against
+    /// the schemas of each destination topic and publishes all valid
+    /// destinations in a single publisher batch. Events that matched no rule,
+    /// or failed validation or topic rendering for every matched rule, are
+    /// published to the dead-letter topic in the same batch, which is their
+    /// last delivery.
+    async fn deliver(&self, events: &[Event]) -> Result<Vec<Vec<Delivery>>, GatewayError> {
+        let routings = self.routings().await?;
+
+        let mut topic_schemas: HashMap<Topic, Vec<DataSchema>> = HashMap::new();
+        let mut deliveries: Vec<Vec<Delivery>> = Vec::with_capacity(events.len());
+        let mut requests = Vec::new();
+        let mut pending = Vec::new();
+        for (event_index, event) in events.iter().enumerate() {
+            let routes = routings.route_all(event);
+            if routes.is_empty() {
+                if let Some(dead_letter) = &self.dead_letter {
+                    pending.push((event_index, Target::DeadLetter));
+                    requests.push((
+                        None,
+                        PublishRequest {
+                            topic: dead_letter.topic.as_str().to_string(),
+                            payload: dead_letter_event(event, None, &[]),
+                            context: PublishContext::default(),
+                        },
+                    ));
+                }
+                deliveries.push(Vec::new());
+                continue;
+            }
+
+            let mut event_deliveries = Vec::with_capacity(routes.len());
+            let mut schema_failure = None;
+            let mut routable = false;
+            for routing in routes {
+                let naming = self.topic_naming(routing.publisher.as_deref());
+                let topic = match routing.topic.render(event, naming) {
+                    Ok(topic) => topic,
+                    Err(err) => {
+                        event_deliveries.push(Delivery {
+                            topic: routing.topic.to_string(),
+                            result: Err(GatewayError::TopicRenderFailed(format!(
+                                "rule {} for event {}: {err}",
+                                routing.id, event.id
+                            ))),
+                            dead_letter: false,
+                        });
+                        continue;
+                    }
+                };
+                if !topic_schemas.contains_key(&topic) {
+                    let schemas = self
+                        .store
+                        .get_validations_for_topic(topic.as_str())
+                        .await
+                        .map_err(GatewayError::from)?;
+                    topic_schemas.insert(topic.clone(), schemas);
+                }
+                let result = match validate_event(event, &topic, &topic_schemas[&topic]) {
+                    Ok(()) => Ok(()),
+                    Err(failure) => {
+                        schema_failure.get_or_insert_with(|| failure.clone());
+                        Err(GatewayError::SchemaInvalid(failure))
+                    }
+                };
+                if result.is_ok() {
+                    routable = true;
+                    pending.push((event_index, Target::Destination(event_deliveries.len())));
+                    requests.push((
+                        routing.publisher.as_deref(),
+                        PublishRequest {
+                            topic: topic.as_str().to_string(),
+                            payload: event.to_owned(),
+                            context: PublishContext {
+                                group_metadata_field: routing.group_metadata_field.clone(),
+                                format: routing.format,
+                                partition_key: routing.partition_key.clone(),
+                                partition_metadata_field: routing.partition_metadata_field.clone(),
+                                mqtt_topic_mapping: routing.mqtt_topic_mapping.clone(),
+                                stream_max_length: routing.stream_max_length,
+                            },
+                        },
+                    ));
+                }
+                event_deliveries.push(Delivery {
+                    topic: topic.into_string(),
+                    result,
+                    dead_letter: false,
+                });
+            }
+
+            if let (Some(dead_letter), false) = (&self.dead_letter, routable) {
+                let topics: Vec<&str> = event_deliveries
+                    .iter()
+                    .map(|delivery| delivery.topic.as_str())
+                    .collect();
+                pending.push((event_index, Target::DeadLetter));
+                requests.push((
+                    None,
+                    PublishRequest {
+                        topic: dead_letter.topic.as_str().to_string(),
+                        payload: dead_letter_event(event, schema_failure.as_ref(), &topics),
+                        context: PublishContext::default(),
+                    },
+                ));
+            }
+            deliveries.push(event_deliveries);
+        }
+
+        let published = self.publish_all(requests).await;
+        for ((event_index, target), result) in pending.into_iter().zip(published) {
+            match target {
+                Target::Destination(delivery_index) => {
+                    deliveries[event_index][delivery_index].result = result;
+                }
+                Target::DeadLetter => {
+                    let Some(dead_letter) = &self.dead_letter else {
+                        continue;
+                    };
+                    if let Err(err) = &result {
+                        error!(
+                            "Failed to publish event {} to dead-letter topic {}: {err}",
+                            events[event_index].id, dead_letter.topic
+                        );
+                    }
+                    deliveries[event_index].push(Delivery {
+                        topic: dead_letter.topic.to_string(),
+                        result,
+                        dead_letter: true,
+                    });
+                }
+            }
+        }
+        Ok(deliveries)
+    }
+
+    /// Whether `delivery` can accept its event: dead-letter copies only do
+    /// under [`DeadLetterPolicy::Accept`].
+    fn accepts(&self, delivery: &Delivery) -> bool {
+        !delivery.dead_letter
+            || self
+                .dead_letter
+                .as_ref()
+                .is_some_and(|dead_letter| dead_letter.policy == DeadLetterPolicy::Accept)
+    }
+
+    /// An event is accepted once at least one of its destinations, or the
+    /// dead-letter topic under [`DeadLetterPolicy::Accept`], published it;
+    /// failed destinations are then only reported in the outcomes. Otherwise
+    /// the error of the first failing destination is returned, preferring
+    /// internal errors over schema failures so producers retry them.
+    fn settle(
+        &self,
+        event: &Event,
+        deliveries: Vec<Delivery>,
+    ) -> Result<Vec<TopicOutcome>, GatewayError> {
+        let outcomes: Vec<TopicOutcome> = deliveries.iter().map(TopicOutcome::from).collect();
+        if deliveries
+            .iter()
+            .any(|delivery| delivery.result.is_ok() && self.accepts(delivery))
+        {
+            for delivery in &deliveries {
+                if let Err(err) = &delivery.result {
+                    warn!(
+                        "Event {} was not published to topic {}: {err}",
+                        event.id, delivery.topic
+                    );
+                }
+            }
+            return Ok(outcomes);
+        }
+
+        let dead_lettered = deliveries
+            .iter()
+            .any(|delivery| delivery.dead_letter && delivery.result.is_ok());
+        let deliveries: Vec<Delivery> = deliveries
+            .into_iter()
+            .filter(|delivery| self.accepts(delivery))
+            .collect();
+        let outcomes: Vec<TopicOutcome> = deliveries.iter().map(TopicOutcome::from).collect();
+        let mut errors: Vec<GatewayError> = deliveries
+            .into_iter()
+            .filter_map(|delivery| delivery.result.err())
+            .collect();
+        let error = match errors
+            .iter()
+            .position(|err| matches!(err, GatewayError::InternalError(_)))
+        {
+            Some(position) => errors.swap_remove(position),
+            None if errors.is_empty() => {
+                GatewayError::NoTopicToRoute(format!("No topic to route event: {:?}", event.id))
+            }
+            None => errors.swap_remove(0),
+        };
+        if dead_lettered {
+            return Err(GatewayError::DeadLetterRejected {
+                error: Box::new(error),
+                outcomes,
+            });
+        }
+        Err(error)
+    }
+
+    /// Publishes every request through its rule's publisher, `None` being the
+    /// default one, with one batch per publisher. Results are in request order.
+    async fn publish_all(
+        &self,
+        requests: Vec<(Option<&str>, PublishRequest<Event>)>,
+    ) -> Vec<Result<(), GatewayError>> {
+        let mut results: Vec<Result<(), GatewayError>> = requests.iter().map(|_| Ok(())).collect();
+        // Request positions and requests, per publisher.
+        type Batch = (Vec<usize>, Vec<PublishRequest<Event>>);
+        let mut batches: HashMap<Option<&str>, Batch> = HashMap::new();
+        for (index, (publisher, request)) in requests.into_iter().enumerate() {
+            let (indices, batch) = batches.entry(publisher).or_default();
+            indices.push(index);
+            batch.push(request);
+        }
+
+        let published = join_all(
+            batches
+                .into_iter()
+                .map(|(name, (indices, batch))| async move {
+                    let results: Vec<Result<(), GatewayError>> = match self.publisher(name) {
+                        Some(publisher) => publisher
+                            .publish_batch(batch)
+                            .await
+                            .into_iter()
+                            .map(|result| result.map_err(GatewayError::from))
+                            .collect(),
+                        None => indices
+                            .iter()
+                            .map(|_| {
+                                Err(GatewayError::InternalError(format!(
+                                    "publisher '{}' is not configured",
+                                    name.unwrap_or_default()
+                                )))
+                            })
+                            .collect(),
+                    };
+                    (indices, results)
+                }),
+        )
+        .await;
+        for (indices, batch_results) in published {
+            for (index, result) in indices.into_iter().zip(batch_results) {
+                results[index] = result;
+            }
+        }
+        results
+    }
+
+    /// Evaluates every rule linearly so that non-matching rules can report
+    /// their failed conditions, then validates each matched destination.
+    async fn explain_event(&self, event: &Event) -> Result<Explanation, GatewayError> {
+        let rules = self
+            .store
+            .get_all_rules()
+            .await
+            .map_err(GatewayError::from)?;
+
+        let mut explained = Vec::new();
+        let mut destinations = Vec::new();
+        for rule in &rules {
+            let failed_conditions = failed_conditions(rule, event);
+            let matched = failed_conditions.is_empty();
+            explained.push(RuleExplanation {
+                id: rule.id,
+                order: rule.order,
+                topic: rule.topic.to_string(),
+                matched,
+                failed_conditions,
+            });
+            if !matched {
+                continue;
+            }
+            destinations.push(self.explain_destination(rule, event).await?);
+            if rule.terminal {
+                break;
+            }
+        }
+
+        let mut outcomes: Vec<TopicOutcome> = destinations
+            .iter()
+            .map(|destination| TopicOutcome {
+                topic: destination.topic.clone(),
+                status: destination.status,
+            })
+            .collect();
+        let dead_letter = self.dead_letter.as_ref().filter(|_| {
+            outcomes.iter().all(|outcome| {
+                matches!(
+                    outcome.status,
+                    EventStatus::SchemaInvalid | EventStatus::TopicRenderFailed
+                )
+            })
+        });
+        if let Some(dead_letter) = dead_letter {
+            if dead_letter.policy == DeadLetterPolicy::Accept {
+                outcomes.push(TopicOutcome {
+                    topic: dead_letter.topic.to_string(),
+                    status: EventStatus::DeadLettered,
+                });
+            }
+        }
+        Ok(Explanation {
+            event_id: event.id,
+            status: aggregate_status(&outcomes),
+            rules: explained,
+            destinations,
+            dead_letter: dead_letter.map(|dead_letter| dead_letter.topic.to_string()),
+        })
+    }
+
+    async fn explain_destination(
+        &self,
+        rule: &TopicRoutingRule,
+        event: &Event,
+    ) -> Result<DestinationExplanation, GatewayError> {
+        let mut destination = DestinationExplanation {
+            rule_id: rule.id,
+            topic: rule.topic.to_string(),
+            publisher: rule.publisher.clone(),
+            status: EventStatus::Published,
+            error: None,
+            schemas: Vec::new(),
+        };
+        if let Err(err) = self.check_publisher(rule) {
+            destination.status = EventStatus::InternalError;
+            destination.error = Some(err.to_string());
+        }
+        let naming = self.topic_naming(rule.publisher.as_deref());
+        let topic = match rule.topic.render(event, naming) {
+            Ok(topic) => topic,
+            Err(err) => {
+                destination.status = EventStatus::TopicRenderFailed;
+                destination.error = Some(err.to_string());
+                return Ok(destination);
+            }
+        };
+        destination.topic = topic.to_string();
+
+        let topic_schemas = self
+            .store
+            .get_validations_for_topic(topic.as_str())
+            .await
+            .map_err(GatewayError::from)?;
+        let json = event_json(event);
+        for schema in selected_schemas(event, &topic_schemas) {
+            let (status, errors) = match json.as_ref().map(|json| schema.schema.validate(json)) {
+                None => (SchemaStatus::Skipped, Vec::new()),
+                Some(Ok(())) => (SchemaStatus::Valid, Vec::new()),
+                Some(Err(errors)) => (SchemaStatus::Invalid, errors),
+            };
+            destination.schemas.push(SchemaExplanation {
+                name: schema.name.clone(),
+                status,
+                errors,
+            });
+        }
+        if destination
+            .schemas
+            .iter()
+            .any(|schema| schema.status == SchemaStatus::Invalid)
+        {
+            destination.status = EventStatus::SchemaInvalid;
+        }
+        Ok(destination)
+    }
+
+    /// Publisher named `name`, `None` being the default one.
+    fn publisher(&self, name: Option<&str>) -> Option<&dyn Publisher<Event>> {
+        match name {
+            None => Some(self.publisher.as_ref()),
+            Some(name) => self.publishers.get(name).map(AsRef::as_ref),
+        }
+    }
+
+    /// Topic naming rules of the publisher named `name`; rules that select an
+    /// unknown publisher fail when publishing instead.
+    fn topic_naming(&self, name: Option<&str>) -> TopicNaming {
+        self.publisher(name)
+            .map(|publisher| publisher.topic_naming())
+            .unwrap_or_default()
+    }
+
+    /// Rejects rules that select a publisher which is not configured, an
+    /// output format their publisher cannot write, or a topic their broker
+    /// would refuse.
+    fn check_publisher(&self, rule: &TopicRoutingRule) -> Result<(), GatewayError> {
+        let publisher = self.publisher(rule.publisher.as_deref()).ok_or_else(|| {
+            GatewayError::InvalidRule(format!(
+                "unknown publisher '{}'",
+                rule.publisher.as_deref().unwrap_or_default()
+            ))
+        })?;
+        if let Some(format) = rule
+            .format
+            .filter(|&format| !publisher.supports_format(format))
+        {
+            return Err(GatewayError::InvalidRule(format!(
+                "publisher does not support the {format} format"
+            )));
+        }
+        publisher
+            .topic_naming()
+            .check_template(&rule.topic)
+            .map_err(|err| GatewayError::InvalidRule(format!("topic '{}': {err}", rule.topic)))
+    }
+
+    /// Checks a rule before it is stored, logging the broker's advice on its
+    /// topic.
+    fn check_new_rule(&self, rule: &TopicRoutingRule) -> Result<(), GatewayError> {
+        self.check_publisher(rule)?;
+        let naming = self.topic_naming(rule.publisher.as_deref());
+        if let Some(warning) = naming.warning(&rule.topic) {
+            warn!("Routing rule {} topic '{}': {warning}", rule.id, rule.topic);
+        }
+        Ok(())
+    }
+
     pub fn new(
         publisher: Box<dyn Publisher<Event> + Sync + Send>,
         store: Box<dyn Storage>,
@@ -65,8 +812,93 @@ impl EventGateway {
         EventGateway {
             publisher,
             store: Arc::new(store),
+            publishers: HashMap::new(),
+            dead_letter: None,
+            routings: RwLock::new(None),
         }
     }
+
+    /// Registers a publisher that routing rules can select by `name`.
+    pub fn with_publisher(
+        mut self,
+        name: impl Into<String>,
+        publisher: Box<dyn Publisher<Event> + Sync + Send>,
+    ) -> Self {
+        self.publishers.insert(name.into(), publisher);
+        self
+    }
+
+    /// Publishes unroutable and invalid events to `config.topic`, which must
+    /// be a valid topic of the default publisher.
+    pub fn with_dead_letter(
+        mut self,
+        config: DeadLetterConfig,
+    ) -> Result<Self, TopicValidationError> {
+        self.dead_letter = Some(DeadLetter {
+            topic: self.publisher.topic_naming().topic(config.topic)?,
+            policy: config.policy,
+        });
+        Ok(self)
+    }
+}
+
+/// Schemas of `topic_schemas` registered for the event's type and version.
+fn selected_schemas<'a>(
+    event: &'a Event,
+    topic_schemas: &'a [DataSchema],
+) -> impl Iterator<Item = &'a DataSchema> {
+    topic_schemas
+        .iter()
+        .filter(|&v| v.event_type == event.event_type && v.event_version == event.event_version)
+}
+
+/// Event data as a JSON value, or `None` when it is not JSON and is therefore
+/// not validated.
+fn event_json(event: &Event) -> Option<Value> {
+    match &event.data {
+        Data::Json(j) => Some(Value::Object(
+            j.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Map<_, _>>(),
+        )),
+        Data::String(_) | Data::Binary(_) => None,
+    }
+}
+
+/// Validates the event data against every schema of `topic_schemas` that was
+/// registered for the event's type and version, reporting all failing schemas.
+fn validate_event(
+    event: &Event,
+    topic: &Topic,
+    topic_schemas: &[DataSchema],
+) -> Result<(), SchemaValidationFailure> {
+    let Some(json) = event_json(event) else {
+        return Ok(());
+    };
+    debug!("Validating schema for event data: {json} [topic={topic}]");
+
+    // Collect validation errors from all schemas
+    let validation_errors: Vec<SchemaErrors> = selected_schemas(event, topic_schemas)
+        .filter_map(|schema| {
+            schema
+                .schema
+                .validate(&json)
+                .err()
+                .map(|errors| SchemaErrors {
+                    name: schema.name.clone(),
+                    errors,
+                })
+        })
+        .collect();
+
+    if validation_errors.is_empty() {
+        return Ok(());
+    }
+    Err(SchemaValidationFailure {
+        event_id: event.id,
+        topic: topic.to_string(),
+        schemas: validation_errors,
+    })
 }
 
 impl From<StorageError> for GatewayError {
@@ -89,89 +921,73 @@ impl From<PublisherError> for GatewayError {
 
 #[async_trait]
 impl GateWay for EventGateway {
-    async fn handle(&self, event: &Event) -> Result<(), GatewayError> {
-        let rules = self
-            .store
-            .get_all_rules()
-            .await
-            .map_err(GatewayError::from)?;
-        let routings = TopicRoutings { rules };
-
-        match routings.route(event) {
-            Some(routing) => {
-                let topic_schemas = self
-                    .store
-                    .get_validations_for_topic(routing.topic.as_str())
-                    .await
-                    .map_err(GatewayError::from)?;
-                let schemas: Vec<&DataSchema> = topic_schemas
+    async fn handle(
+        &self,
+        event: &Event,
+        _idempotency_key: Option<&str>,
+    ) -> Result<Vec<TopicOutcome>, GatewayError> {
+        let deliveries = self
+            .deliver(std::slice::from_ref(event))
+            .await?
+            .pop()
+            .unwrap_or_default();
+        self.settle(event, deliveries)
+    }
+
+    async fn publish_batch(
+        &self,
+        events: &[Event],
+        _idempotency_key: Option<&str>,
+    ) -> Result<Vec<EventResult>, GatewayError> {
+        let deliveries = self.deliver(events).await?;
+        Ok(events
+            .iter()
+            .zip(deliveries)
+            .map(|(event, deliveries)| {
+                let topics: Vec<TopicOutcome> = deliveries
                     .iter()
-                    .filter(|&v| {
-                        v.event_type == event.event_type && v.event_version == event.event_version
+                    .inspect(|delivery| {
+                        if let Err(GatewayError::InternalError(err)) = &delivery.result {
+                            error!(
+                                "Failed to publish event {} in batch to topic {}: {err}",
+                                event.id, delivery.topic
+                            );
+                        }
                     })
+                    .map(TopicOutcome::from)
                     .collect();
-
-                let validation_errors = match &event.data {
-                    Data::Json(j) => {
-                        let json = Value::Object(
-                            j.iter()
-                                .map(|(k, v)| (k.clone(), v.clone()))
-                                .collect::<Map<_, _>>(),
-                        );
-                        debug!(
-                            "Validating schema for event data: {} [topic={}]",
-                            json, routing.topic
-                        );
-
-                        // Collect validation errors from all schemas
-                        let mut schema_errors = Vec::new();
-                        for schema in &schemas {
-                            if let Err(errors) = schema.schema.validate(&json) {
-                                let error_details = errors
-                                    .iter()
-                                    .map(|e| {
-                                        format!(
-                                            "Field '{}': {} (at schema path: {})",
-                                            e.instance_path, e.message, e.schema_path
-                                        )
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join("; ");
-                                schema_errors.push((schema, error_details));
-                            }
-                        }
-                        schema_errors
-                    }
-                    Data::String(_) => Vec::new(),
-                    Data::Binary(_) => Vec::new(),
-                };
-
-                if !validation_errors.is_empty() {
-                    let (failed_schema, error_details) = &validation_errors[0];
-                    let error_msg = format!(
-                        "Event {} failed schema validation for '{}': {}",
-                        event.id, failed_schema.name, error_details
-                    );
-
-                    return Err(GatewayError::SchemaInvalid(error_msg));
+                // A dead-letter copy under the reject policy is listed
+                // without accepting the event.
+                let accepting: Vec<TopicOutcome> = deliveries
+                    .iter()
+                    .filter(|delivery| self.accepts(delivery))
+                    .map(TopicOutcome::from)
+                    .collect();
+                EventResult {
+                    id: event.id,
+                    status: aggregate_status(&accepting),
+                    topics,
                 }
+            })
+            .collect())
+    }
 
-                self.publisher
-                    .publish_one(
-                        routing.topic.as_str(),
-                        event.to_owned(),
-                        PublishContext {
-                            group_metadata_field: routing.group_metadata_field.clone(),
-                        },
-                    )
-                    .await
-                    .map_err(GatewayError::from)
-            }
-            None => Err(GatewayError::NoTopicToRoute(format!(
-                "No topic to route event: {:?}",
-                event.id
-            ))),
+    async fn explain(&self, event: &Event) -> Result<Explanation, GatewayError> {
+        self.explain_event(event).await
+    }
+
+    fn disconnected_publishers(&self) -> Vec<String> {
+        let mut disconnected: Vec<String> = self
+            .publishers
+            .iter()
+            .filter(|(_, publisher)| !publisher.is_connected())
+            .map(|(name, _)| name.clone())
+            .collect();
+        disconnected.sort();
+        if !self.publisher.is_connected() {
+            disconnected.insert(0, "default".to_string());
         }
+        disconnected
     }
 
     async fn add_topic_validation(&self, v: &TopicValidationConfig) -> Result<(), GatewayError> {
@@ -189,6 +1005,7 @@ impl GateWay for EventGateway {
     }
 
     async fn add_routing_rule(&self, rule: &TopicRoutingRule) -> Result<(), GatewayError> {
+        self.check_new_rule(rule)?;
         self.store.add_rule(rule).await.map_err(GatewayError::from)
     }
 
@@ -197,6 +1014,7 @@ impl GateWay for EventGateway {
         id: Uuid,
         rule: &TopicRoutingRule,
     ) -> Result<(), GatewayError> {
+        self.check_new_rule(rule)?;
         self.store
             .update_rule(id, rule)
             .await
@@ -223,3 +1041,374 @@ impl GateWay for EventGateway {
             .map_err(GatewayError::from)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::model::expressions::{Condition, StringExpression};
+    use crate::model::topic::TopicTemplate;
+    use crate::store::storage::InMemoryStorage;
+
+    #[derive(Clone, Default)]
+    struct RecordingPublisher(Arc<Mutex<Vec<(String, Event)>>>);
+
+    #[async_trait]
+    impl Publisher<Event> for RecordingPublisher {
+        async fn publish_one(
+            &self,
+            topic: &str,
+            payload: Event,
+            _context: PublishContext,
+        ) -> Result<(), PublisherError> {
+            self.0.lock().unwrap().push((topic.to_string(), payload));
+            Ok(())
+        }
+    }
+
+    async fn gateway(policy: DeadLetterPolicy) -> (EventGateway, RecordingPublisher) {
+        let storage = InMemoryStorage::new();
+        storage
+            .add_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders").unwrap(),
+                event_type_condition: Condition::ONE(StringExpression::Equals {
+                    value: "order.created".to_string(),
+                }),
+                event_version_condition: None,
+                field_conditions: Default::default(),
+                payload_conditions: Vec::new(),
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+            })
+            .await
+            .unwrap();
+        storage
+            .add_topic_validation(
+                &serde_json::from_value(serde_json::json!({
+                    "id": Uuid::new_v4(),
+                    "topic": "orders",
+                    "schema": {
+                        "name": "order",
+                        "description": null,
+                        "event_type": "order.created",
+                        "event_version": null,
+                        "metadata": null,
+                        "schema": {
+                            "type": "json",
+                            "data": {"type": "object", "required": ["order_id"]}
+                        }
+                    }
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let publisher = RecordingPublisher::default();
+        let gateway = EventGateway::new(Box::new(publisher.clone()), Box::new(storage))
+            .with_dead_letter(DeadLetterConfig {
+                topic: "events.dlq".to_string(),
+                policy,
+            })
+            .unwrap();
+        (gateway, publisher)
+    }
+
+    fn event(event_type: &str) -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: event_type.to_string(),
+            event_version: None,
+            metadata: HashMap::new(),
+            transport_metadata: None,
+            data_type: None,
+            data: Data::Json(HashMap::new()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        }
+    }
+
+    fn dead_letter_metadata(publisher: &RecordingPublisher) -> Option<HashMap<String, String>> {
+        let published = publisher.0.lock().unwrap();
+        let (topic, event) = published.last()?;
+        assert_eq!(topic, "events.dlq");
+        event.transport_metadata.clone()
+    }
+
+    #[tokio::test]
+    async fn accepts_dead_lettered_events() {
+        let (gateway, publisher) = gateway(DeadLetterPolicy::Accept).await;
+
+        let outcomes = gateway.handle(&event("user.created"), None).await.unwrap();
+        assert_eq!(
+            outcomes,
+            vec![TopicOutcome {
+                topic: "events.dlq".to_string(),
+                status: EventStatus::DeadLettered,
+            }]
+        );
+        let metadata = dead_letter_metadata(&publisher).unwrap();
+        assert_eq!(metadata["dead_letter_reason"], "no_route");
+
+        let results = gateway
+            .publish_batch(&[event("order.created")], None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].status, EventStatus::DeadLettered);
+        assert_eq!(
+            results[0].topics[0],
+            TopicOutcome {
+                topic: "orders".to_string(),
+                status: EventStatus::SchemaInvalid,
+            }
+        );
+        let metadata = dead_letter_metadata(&publisher).unwrap();
+        assert_eq!(metadata["dead_letter_reason"], "schema_invalid");
+        assert_eq!(metadata["dead_letter_schema"], "order");
+        assert_eq!(metadata["dead_letter_topics"], "orders");
+        assert!(metadata["dead_letter_errors"].contains("order_id"));
+    }
+
+    #[tokio::test]
+    async fn dead_letters_events_without_a_publishable_destination() {
+        let (gateway, publisher) = gateway(DeadLetterPolicy::Accept).await;
+        gateway
+            .add_routing_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: -1,
+                topic: TopicTemplate::new("orders.{metadata.region}").unwrap(),
+                event_type_condition: Condition::ANY,
+                event_version_condition: None,
+                field_conditions: Default::default(),
+                payload_conditions: Vec::new(),
+                description: None,
+                group_metadata_field: None,
+                terminal: false,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+            })
+            .await
+            .unwrap();
+
+        let results = gateway
+            .publish_batch(&[event("order.created")], None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].status, EventStatus::DeadLettered);
+        let statuses: Vec<EventStatus> = results[0]
+            .topics
+            .iter()
+            .map(|outcome| outcome.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                EventStatus::TopicRenderFailed,
+                EventStatus::SchemaInvalid,
+                EventStatus::DeadLettered
+            ]
+        );
+        let metadata = dead_letter_metadata(&publisher).unwrap();
+        assert_eq!(metadata["dead_letter_reason"], "schema_invalid");
+        assert_eq!(
+            metadata["dead_letter_topics"],
+            "orders.{metadata.region},orders"
+        );
+
+        let outcomes = gateway.handle(&event("user.created"), None).await.unwrap();
+        assert_eq!(outcomes[0].status, EventStatus::TopicRenderFailed);
+        assert_eq!(outcomes[1].status, EventStatus::DeadLettered);
+        let metadata = dead_letter_metadata(&publisher).unwrap();
+        assert_eq!(metadata["dead_letter_reason"], "topic_render_failed");
+    }
+
+    #[tokio::test]
+    async fn rejects_dead_lettered_events() {
+        let (gateway, publisher) = gateway(DeadLetterPolicy::Reject).await;
+
+        let Err(GatewayError::DeadLetterRejected { error, outcomes }) =
+            gateway.handle(&event("user.created"), None).await
+        else {
+            panic!("event was not rejected after dead-lettering");
+        };
+        assert!(matches!(*error, GatewayError::NoTopicToRoute(_)));
+        assert!(outcomes.is_empty());
+        assert!(dead_letter_metadata(&publisher).is_some());
+
+        let Err(GatewayError::DeadLetterRejected { error, outcomes }) =
+            gateway.handle(&event("order.created"), None).await
+        else {
+            panic!("event was not rejected after dead-lettering");
+        };
+        assert!(matches!(*error, GatewayError::SchemaInvalid(_)));
+        assert_eq!(outcomes[0].status, EventStatus::SchemaInvalid);
+        assert_eq!(publisher.0.lock().unwrap().len(), 2);
+
+        let results = gateway
+            .publish_batch(&[event("order.created")], None)
+            .await
+            .unwrap();
+        assert_eq!(results[0].status, EventStatus::SchemaInvalid);
+        assert_eq!(
+            results[0].topics[1],
+            TopicOutcome {
+                topic: "events.dlq".to_string(),
+                status: EventStatus::DeadLettered,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_through_the_rule_publisher() {
+        let default = RecordingPublisher::default();
+        let queue = RecordingPublisher::default();
+        let gateway =
+            EventGateway::new(Box::new(default.clone()), Box::new(InMemoryStorage::new()))
+                .with_publisher("queue", Box::new(queue.clone()));
+        let rule = |order: i32, event_type: &str, publisher: Option<&str>| TopicRoutingRule {
+            id: Uuid::new_v4(),
+            order,
+            topic: TopicTemplate::new(event_type).unwrap(),
+            event_type_condition: Condition::ONE(StringExpression::Equals {
+                value: event_type.to_string(),
+            }),
+            event_version_condition: None,
+            field_conditions: Default::default(),
+            payload_conditions: Vec::new(),
+            description: None,
+            group_metadata_field: None,
+            terminal: true,
+            publisher: publisher.map(str::to_string),
+            format: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            mqtt_topic_mapping: None,
+            stream_max_length: None,
+        };
+
+        gateway
+            .add_routing_rule(&rule(0, "order.created", Some("queue")))
+            .await
+            .unwrap();
+        gateway
+            .add_routing_rule(&rule(1, "user.created", None))
+            .await
+            .unwrap();
+        assert!(matches!(
+            gateway
+                .add_routing_rule(&rule(2, "user.deleted", Some("kafka")))
+                .await,
+            Err(GatewayError::InvalidRule(_))
+        ));
+
+        let results = gateway
+            .publish_batch(&[event("order.created"), event("user.created")], None)
+            .await
+            .unwrap();
+        assert!(results
+            .iter()
+            .all(|result| result.status == EventStatus::Published));
+        assert_eq!(queue.0.lock().unwrap()[0].0, "order.created");
+        assert_eq!(default.0.lock().unwrap()[0].0, "user.created");
+        assert_eq!(queue.0.lock().unwrap().len(), 1);
+        assert_eq!(default.0.lock().unwrap().len(), 1);
+    }
+
+    struct QueuePublisher;
+
+    #[async_trait]
+    impl Publisher<Event> for QueuePublisher {
+        async fn publish_one(
+            &self,
+            _topic: &str,
+            _payload: Event,
+            _context: PublishContext,
+        ) -> Result<(), PublisherError> {
+            Ok(())
+        }
+
+        fn topic_naming(&self) -> TopicNaming {
+            TopicNaming::Pgmq
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_topics_the_broker_refuses() {
+        let gateway = EventGateway::new(
+            Box::new(RecordingPublisher::default()),
+            Box::new(InMemoryStorage::new()),
+        )
+        .with_publisher("queue", Box::new(QueuePublisher));
+        let rule = |topic: &str| TopicRoutingRule {
+            id: Uuid::new_v4(),
+            order: 0,
+            topic: TopicTemplate::new(topic).unwrap(),
+            event_type_condition: Condition::ANY,
+            event_version_condition: None,
+            field_conditions: Default::default(),
+            payload_conditions: Vec::new(),
+            description: None,
+            group_metadata_field: None,
+            terminal: true,
+            publisher: Some("queue".to_string()),
+            format: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            mqtt_topic_mapping: None,
+            stream_max_length: None,
+        };
+
+        gateway
+            .add_routing_rule(&rule("orders_{metadata.region}"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            gateway.add_routing_rule(&rule("orders.created")).await,
+            Err(GatewayError::InvalidRule(_))
+        ));
+
+        let mut event = event("order.created");
+        event
+            .metadata
+            .insert("region".to_string(), "eu-west".to_string());
+        let results = gateway.publish_batch(&[event], None).await.unwrap();
+        assert_eq!(results[0].status, EventStatus::TopicRenderFailed);
+    }
+
+    #[tokio::test]
+    async fn explains_without_publishing() {
+        let (gateway, publisher) = gateway(DeadLetterPolicy::Accept).await;
+
+        let explanation = gateway.explain(&event("order.created")).await.unwrap();
+        assert_eq!(explanation.status, EventStatus::DeadLettered);
+        assert_eq!(explanation.dead_letter.as_deref(), Some("events.dlq"));
+        assert!(explanation.rules[0].matched);
+        let destination = &explanation.destinations[0];
+        assert_eq!(destination.topic, "orders");
+        assert_eq!(destination.status, EventStatus::SchemaInvalid);
+        assert_eq!(destination.schemas[0].name, "order");
+        assert_eq!(destination.schemas[0].status, SchemaStatus::Invalid);
+
+        let explanation = gateway.explain(&event("user.created")).await.unwrap();
+        assert_eq!(
+            explanation.rules[0].failed_conditions,
+            vec!["eventTypeCondition".to_string()]
+        );
+        assert!(explanation.destinations.is_empty());
+        assert!(publisher.0.lock().unwrap().is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use crate::{
-    gateway::gateway::{GateWay, GatewayError},
+    gateway::gateway::{
+        EventResult, EventStatus, Explanation, GateWay, GatewayError, TopicOutcome,
+    },
     model::event::Event,
     model::routing::{TopicRoutingRule, TopicValidationConfig},
 };
@@ -38,6 +40,15 @@ where
             histogram,
         })
     }
+
+    fn count(&self, event: &Event, result: &str) {
+        let event_type_label = event.event_type.as_str();
+        let event_version_label = event.event_version.as_deref().unwrap_or("unknown");
+        let source_label = event.origin.as_deref().unwrap_or("unknown");
+        self.counters
+            .with_label_values(&[event_type_label, event_version_label, source_label, result])
+            .inc();
+    }
 }
 
 #[async_trait]
@@ -45,36 +56,56 @@ impl<T> GateWay for MeteredEventGateway<T>
 where
     T: GateWay,
 {
-    async fn handle(&self, event: &Event) -> Result<(), GatewayError> {
+    async fn handle(
+        &self,
+        event: &Event,
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<TopicOutcome>, GatewayError> {
         let timer = self.histogram.with_label_values(&["handle"]).start_timer();
-        let result = self.gateway.handle(event).await;
+        let result = self.gateway.handle(event, idempotency_key).await;
         timer.observe_duration();
-        let event_type_label = event.event_type.as_str(); // Change as per actual field type and structure
-        let event_version_label = event.event_version.as_deref().unwrap_or("unknown");
-        let source_label = event.origin.as_deref().unwrap_or("unknown");
         match &result {
-            Ok(_) => {
-                let labels = [
-                    event_type_label,
-                    event_version_label,
-                    source_label,
-                    "success",
-                ];
-                self.counters.with_label_values(&labels).inc();
-            }
-            Err(_) => {
-                let labels = [
-                    event_type_label,
-                    event_version_label,
-                    source_label,
-                    "failure",
-                ];
-                self.counters.with_label_values(&labels).inc();
+            Ok(_) => self.count(event, "success"),
+            Err(_) => self.count(event, "failure"),
+        }
+        result
+    }
+
+    async fn publish_batch(
+        &self,
+        events: &[Event],
+        idempotency_key: Option<&str>,
+    ) -> Result<Vec<EventResult>, GatewayError> {
+        let timer = self
+            .histogram
+            .with_label_values(&["publish_batch"])
+            .start_timer();
+        let result = self.gateway.publish_batch(events, idempotency_key).await;
+        timer.observe_duration();
+        match &result {
+            Ok(results) => {
+                for (event, outcome) in events.iter().zip(results) {
+                    match outcome.status {
+                        EventStatus::Published | EventStatus::DeadLettered => {
+                            self.count(event, "success")
+                        }
+                        _ => self.count(event, "failure"),
+                    }
+                }
             }
+            Err(_) => events.iter().for_each(|event| self.count(event, "failure")),
         }
         result
     }
 
+    async fn explain(&self, event: &Event) -> Result<Explanation, GatewayError> {
+        self.gateway.explain(event).await
+    }
+
+    fn disconnected_publishers(&self) -> Vec<String> {
+        self.gateway.disconnected_publishers()
+    }
+
     async fn add_topic_validation(
         &self,
         v: &crate::model::routing::TopicValidationConfig,
@@ -1,2 +1,3 @@
+pub mod deduplicated;
 pub mod gateway;
 pub mod metered;
@@ -0,0 +1,113 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{FromRequest, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+use hyper::{header::CONTENT_TYPE, HeaderMap};
+use log::warn;
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+
+use crate::model::cloudevent::{
+    media_type, CloudEvent, CloudEventError, BATCH_CONTENT_TYPE, STRUCTURED_CONTENT_TYPE,
+};
+use crate::model::event::Event;
+
+const BINARY_ATTRIBUTE_PREFIX: &str = "ce-";
+
+/// An event in the gateway's own JSON format, or a CloudEvent in structured
+/// (`application/cloudevents+json`) or binary (`ce-*` headers) mode.
+pub(super) struct IngestedEvent(pub Event);
+
+/// A JSON array of events in the gateway's own format, or a CloudEvents
+/// batch (`application/cloudevents-batch+json`).
+pub(super) struct IngestedEvents(pub Vec<Event>);
+
+fn content_type(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn rejection(err: CloudEventError) -> Response {
+    warn!("Rejected CloudEvent: {err}");
+    Response::builder()
+        .status(400)
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            serde_json::json!({ "error": format!("Invalid CloudEvent: {err}") }).to_string(),
+        ))
+        .unwrap()
+}
+
+/// Percent-decoded `ce-` header values, keyed by attribute name.
+fn binary_attributes(headers: &HeaderMap) -> Result<HashMap<String, String>, CloudEventError> {
+    let mut attributes = HashMap::new();
+    for (name, value) in headers {
+        let Some(attribute) = name.as_str().strip_prefix(BINARY_ATTRIBUTE_PREFIX) else {
+            continue;
+        };
+        let value = value
+            .to_str()
+            .ok()
+            .and_then(|value| percent_decode_str(value).decode_utf8().ok())
+            .ok_or_else(|| {
+                CloudEventError::Malformed(format!("header '{name}' is not valid UTF-8"))
+            })?;
+        attributes.insert(attribute.to_string(), value.into_owned());
+    }
+    Ok(attributes)
+}
+
+impl<S: Send + Sync> FromRequest<S> for IngestedEvent {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let media_type = content_type(req.headers()).map(media_type);
+        if media_type.as_deref() == Some(STRUCTURED_CONTENT_TYPE) {
+            let body = Bytes::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return CloudEvent::from_json(&body)
+                .and_then(CloudEvent::into_event)
+                .map(IngestedEvent)
+                .map_err(rejection);
+        }
+        if req.headers().contains_key("ce-specversion") {
+            let attributes = binary_attributes(req.headers()).map_err(rejection)?;
+            let content_type = content_type(req.headers()).map(str::to_string);
+            let body = Bytes::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return CloudEvent::from_binary(attributes, content_type.as_deref(), &body)
+                .map(IngestedEvent)
+                .map_err(rejection);
+        }
+        let Json(event) = Json::<Event>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(IngestedEvent(event))
+    }
+}
+
+impl<S: Send + Sync> FromRequest<S> for IngestedEvents {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let media_type = content_type(req.headers()).map(media_type);
+        if media_type.as_deref() == Some(BATCH_CONTENT_TYPE) {
+            let body = Bytes::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return CloudEvent::batch_from_json(&body)
+                .and_then(|batch| batch.into_iter().map(CloudEvent::into_event).collect())
+                .map(IngestedEvents)
+                .map_err(rejection);
+        }
+        let Json(events) = Json::<Vec<Event>>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(IngestedEvents(events))
+    }
+}
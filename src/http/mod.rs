@@ -1,9 +1,12 @@
+mod ingest;
+
 use crate::configuration::ApiConfig;
-use crate::gateway::gateway::GateWay;
-use crate::model::event::Event;
-use crate::model::expressions::Condition;
+use crate::gateway::gateway::{EventStatus, GateWay, GatewayError, SchemaValidationFailure};
+use crate::model::expressions::{Condition, PayloadCondition};
+use crate::model::field::EventField;
+use crate::model::publishing::{MqttTopicMapping, OutputFormat, PartitionKey};
 use crate::model::routing::{DataSchema, TopicRoutingRule, TopicValidationConfig};
-use crate::model::topic::Topic;
+use crate::model::topic::{Topic, TopicTemplate};
 use axum::extract::{FromRequestParts, Path, Request};
 use axum::http::request::Parts;
 use axum::middleware::Next;
@@ -16,23 +19,38 @@ use axum::{
     Json, Router,
 };
 use hyper::header::USER_AGENT;
-use hyper::StatusCode;
+use hyper::{HeaderMap, StatusCode};
 use jwt_authorizer::{Authorizer, JwtAuthorizer, RegisteredClaims};
 use log::{error, warn};
 use prometheus::{Encoder, TextEncoder};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
+use std::num::NonZeroU64;
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 use uuid::Uuid;
 
+use ingest::{IngestedEvent, IngestedEvents};
+
 type GatewayService = dyn GateWay + Send + Sync;
 
 #[derive(Debug, Clone)]
 struct RequestMetadata {
     originator_ip: Option<String>,
     user_agent: Option<String>,
+    idempotency_key: Option<String>,
+    traceparent: Option<String>,
+}
+
+/// Trimmed value of a non-empty header.
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_owned())
 }
 
 impl RequestMetadata {
@@ -75,6 +93,8 @@ impl<S: Sync> FromRequestParts<S> for RequestMetadata {
         Ok(RequestMetadata {
             originator_ip,
             user_agent,
+            idempotency_key: header_value(&parts.headers, "idempotency-key"),
+            traceparent: header_value(&parts.headers, "traceparent"),
         })
     }
 }
@@ -93,8 +113,19 @@ struct CreateRoutingRuleRequest {
     topic: String,
     event_type_condition: Condition,
     event_version_condition: Option<Condition>,
+    #[serde(default)]
+    field_conditions: BTreeMap<EventField, Condition>,
+    #[serde(default)]
+    payload_conditions: Vec<PayloadCondition>,
     description: Option<String>,
     group_metadata_field: Option<String>,
+    terminal: Option<bool>,
+    publisher: Option<String>,
+    format: Option<OutputFormat>,
+    partition_key: Option<PartitionKey>,
+    partition_metadata_field: Option<String>,
+    mqtt_topic_mapping: Option<MqttTopicMapping>,
+    stream_max_length: Option<NonZeroU64>,
 }
 
 pub async fn app_router(
@@ -133,7 +164,8 @@ fn build_router(
         .route("/topic-validations", get(read_topic_validations))
         .route("/topic-validations", post(create_topic_validation))
         .route("/topic-validations/{id}", delete(delete_topic_validation))
-        .route("/health-check", get(health_check));
+        .route("/health-check", get(health_check))
+        .route("/ready", get(readiness));
 
     if metrics_enabled {
         public_routes = public_routes.route("/metrics", get(metrics_endpoint));
@@ -142,10 +174,14 @@ fn build_router(
     let ingestion_routes = match authorization {
         Some(layer) => Router::new()
             .route("/event", post(handle_event))
+            .route("/events", post(handle_events))
+            .route("/event/explain", post(explain_event))
             .with_state(Arc::clone(&service))
             .route_layer(axum::middleware::from_fn_with_state(layer, authorize_event)),
         None => Router::new()
             .route("/event", post(handle_event))
+            .route("/events", post(handle_events))
+            .route("/event/explain", post(explain_event))
             .with_state(Arc::clone(&service))
             .layer(Extension(Option::<RegisteredClaims>::None)),
     };
@@ -203,6 +239,8 @@ async fn extract_request_metadata(
     let metadata = RequestMetadata {
         originator_ip,
         user_agent,
+        idempotency_key: header_value(req.headers(), "idempotency-key"),
+        traceparent: header_value(req.headers(), "traceparent"),
     };
     req.extensions_mut().insert(metadata);
 
@@ -217,6 +255,23 @@ async fn health_check() -> Response {
         .unwrap()
 }
 
+async fn readiness(State(service): State<Arc<GatewayService>>) -> Response {
+    let disconnected = service.disconnected_publishers();
+    let (status, body) = if disconnected.is_empty() {
+        (200, serde_json::json!({ "status": "ready" }))
+    } else {
+        (
+            503,
+            serde_json::json!({ "status": "unavailable", "disconnectedPublishers": disconnected }),
+        )
+    };
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
 async fn metrics_endpoint() -> Response {
     let encoder = TextEncoder::new();
     let metric_families = prometheus::gather();
@@ -229,12 +284,10 @@ async fn metrics_endpoint() -> Response {
         .unwrap()
 }
 
-async fn handle_event(
-    State(service): State<Arc<GatewayService>>,
-    Extension(claims): Extension<Option<RegisteredClaims>>,
-    Extension(metadata): Extension<RequestMetadata>,
-    Json(mut event): Json<Event>,
-) -> Result<Response, Response> {
+fn transport_metadata(
+    claims: Option<RegisteredClaims>,
+    metadata: &RequestMetadata,
+) -> HashMap<String, String> {
     let mut transport_meta = HashMap::new();
     if let Some(claims) = claims {
         if let Some(sub) = claims.sub {
@@ -245,40 +298,166 @@ async fn handle_event(
         }
     }
     transport_meta.extend(metadata.to_hash_map_string());
-    event.transport_metadata = Some(transport_meta);
-    let result = service.handle(&event).await;
+    transport_meta
+}
+
+async fn handle_event(
+    State(service): State<Arc<GatewayService>>,
+    Extension(claims): Extension<Option<RegisteredClaims>>,
+    Extension(metadata): Extension<RequestMetadata>,
+    IngestedEvent(mut event): IngestedEvent,
+) -> Result<Response, Response> {
+    event.transport_metadata = Some(transport_metadata(claims, &metadata));
+    event.ingest.traceparent = metadata.traceparent.clone();
+    let result = service
+        .handle(&event, metadata.idempotency_key.as_deref())
+        .await;
     match result {
-        Ok(_) => Ok(Response::builder()
+        Ok(outcomes) => {
+            // Fan-out can publish to some destinations and fail others; an
+            // event without any published destination was dead-lettered.
+            let published = outcomes
+                .iter()
+                .filter(|outcome| outcome.status == EventStatus::Published)
+                .count();
+            let (status, body_status) = if published == outcomes.len() {
+                (200, "success")
+            } else if published > 0 {
+                (207, "partial")
+            } else {
+                (202, "dead_lettered")
+            };
+            Ok(Response::builder()
+                .status(status)
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({"status": body_status, "topics": outcomes}).to_string(),
+                ))
+                .unwrap())
+        }
+        Err(err) => Ok(rejection_response(err)),
+    }
+}
+
+/// Response for an event the gateway rejected.
+fn rejection_response(err: GatewayError) -> Response {
+    match err {
+        GatewayError::SchemaInvalid(failure) => {
+            warn!("Event rejected by schema validation: {failure}");
+            schema_problem(&failure)
+        }
+        GatewayError::NoTopicToRoute(err) => {
+            warn!("Event has no routing destination: {err}");
+            Response::builder()
+                .status(406)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"error": "no destination found"}"#))
+                .unwrap()
+        }
+        GatewayError::TopicRenderFailed(err) => {
+            warn!("Event topic could not be rendered: {err}");
+            Response::builder()
+                .status(422)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"error": "topic rendering failed"}"#))
+                .unwrap()
+        }
+        GatewayError::DuplicateInFlight(key) => {
+            warn!("Event with idempotency key {key} is already being handled");
+            Response::builder()
+                .status(409)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"error": "event is already being handled"}"#))
+                .unwrap()
+        }
+        GatewayError::InternalError(err) | GatewayError::InvalidRule(err) => {
+            error!("Failed to handle event: {err}");
+            Response::builder()
+                .status(500)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"error": "internal server error"}"#))
+                .unwrap()
+        }
+        GatewayError::DeadLetterRejected { error, .. } => rejection_response(*error),
+    }
+}
+
+/// RFC 7807 problem details listing every schema the event failed.
+fn schema_problem(failure: &SchemaValidationFailure) -> Response {
+    let problem = serde_json::json!({
+        "type": "about:blank",
+        "title": "Schema validation failed",
+        "status": 400,
+        "detail": format!(
+            "Event {} failed validation against {} schema(s) for topic {}",
+            failure.event_id,
+            failure.schemas.len(),
+            failure.topic
+        ),
+        "event_id": failure.event_id,
+        "topic": failure.topic,
+        "schemas": failure.schemas,
+    });
+    Response::builder()
+        .status(400)
+        .header("Content-Type", "application/problem+json")
+        .body(Body::from(problem.to_string()))
+        .unwrap()
+}
+
+async fn explain_event(
+    State(service): State<Arc<GatewayService>>,
+    Extension(claims): Extension<Option<RegisteredClaims>>,
+    Extension(metadata): Extension<RequestMetadata>,
+    IngestedEvent(mut event): IngestedEvent,
+) -> Result<Response, Response> {
+    event.transport_metadata = Some(transport_metadata(claims, &metadata));
+    event.ingest.traceparent = metadata.traceparent.clone();
+    match service.explain(&event).await {
+        Ok(explanation) => Ok(Response::builder()
             .status(200)
             .header("Content-Type", "application/json")
-            .body(Body::from(r#"{"status": "success"}"#))
+            .body(Body::from(serde_json::to_string(&explanation).unwrap()))
             .unwrap()),
-        Err(err) => match err {
-            crate::gateway::gateway::GatewayError::SchemaInvalid(err) => {
-                warn!("Event rejected by schema validation: {err}");
-                Ok(Response::builder()
-                    .status(400)
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(r#"{"error": "schema validation failed"}"#))
-                    .unwrap())
-            }
-            crate::gateway::gateway::GatewayError::NoTopicToRoute(err) => {
-                warn!("Event has no routing destination: {err}");
-                Ok(Response::builder()
-                    .status(406)
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(r#"{"error": "no destination found"}"#))
-                    .unwrap())
-            }
-            crate::gateway::gateway::GatewayError::InternalError(err) => {
-                error!("Failed to handle event: {err}");
-                Ok(Response::builder()
-                    .status(500)
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(r#"{"error": "internal server error"}"#))
-                    .unwrap())
-            }
-        },
+        Err(err) => {
+            error!("Failed to explain event: {err}");
+            Ok(Response::builder()
+                .status(500)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"error": "internal server error"}"#))
+                .unwrap())
+        }
+    }
+}
+
+async fn handle_events(
+    State(service): State<Arc<GatewayService>>,
+    Extension(claims): Extension<Option<RegisteredClaims>>,
+    Extension(metadata): Extension<RequestMetadata>,
+    IngestedEvents(mut events): IngestedEvents,
+) -> Result<Response, Response> {
+    let transport_meta = transport_metadata(claims, &metadata);
+    for event in events.iter_mut() {
+        event.transport_metadata = Some(transport_meta.clone());
+        event.ingest.traceparent = metadata.traceparent.clone();
+    }
+    let result = service
+        .publish_batch(&events, metadata.idempotency_key.as_deref())
+        .await;
+    match result {
+        Ok(results) => Ok(Response::builder()
+            .status(200)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&results).unwrap()))
+            .unwrap()),
+        Err(err) => {
+            error!("Failed to handle event batch: {err}");
+            Ok(Response::builder()
+                .status(500)
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"error": "internal server error"}"#))
+                .unwrap())
+        }
     }
 }
 
@@ -286,7 +465,7 @@ async fn create_routing_rule(
     State(service): State<Arc<GatewayService>>,
     Json(request): Json<CreateRoutingRuleRequest>,
 ) -> Result<Response, Response> {
-    let topic = Topic::new(&request.topic).map_err(|e| {
+    let topic = TopicTemplate::new(&request.topic).map_err(|e| {
         Response::builder()
             .status(400)
             .header("Content-Type", "application/json")
@@ -300,11 +479,26 @@ async fn create_routing_rule(
         topic,
         event_type_condition: request.event_type_condition,
         event_version_condition: request.event_version_condition,
+        field_conditions: request.field_conditions,
+        payload_conditions: request.payload_conditions,
         description: request.description,
         group_metadata_field: request
             .group_metadata_field
             .map(|field| field.trim().to_string())
             .filter(|field| !field.is_empty()),
+        terminal: request.terminal.unwrap_or(true),
+        publisher: request
+            .publisher
+            .map(|publisher| publisher.trim().to_string())
+            .filter(|publisher| !publisher.is_empty()),
+        format: request.format,
+        partition_key: request.partition_key,
+        partition_metadata_field: request
+            .partition_metadata_field
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty()),
+        mqtt_topic_mapping: request.mqtt_topic_mapping,
+        stream_max_length: request.stream_max_length,
     };
     let result = service.add_routing_rule(&rule).await;
     match result {
@@ -313,6 +507,13 @@ async fn create_routing_rule(
             .header("Content-Type", "application/json")
             .body(Body::from(r#"{"status": "success"}"#))
             .unwrap()),
+        Err(crate::gateway::gateway::GatewayError::InvalidRule(err)) => Err(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "error": format!("Invalid routing rule: {err}") }).to_string(),
+            ))
+            .unwrap()),
         Err(err) => {
             error!("Failed to create routing rule: {err}");
             Ok(Response::builder()
@@ -329,7 +530,7 @@ async fn update_routing_rule(
     Path(id): Path<Uuid>,
     Json(request): Json<CreateRoutingRuleRequest>,
 ) -> Result<Response, Response> {
-    let topic = Topic::new(&request.topic).map_err(|e| {
+    let topic = TopicTemplate::new(&request.topic).map_err(|e| {
         Response::builder()
             .status(400)
             .header("Content-Type", "application/json")
@@ -343,11 +544,26 @@ async fn update_routing_rule(
         topic,
         event_type_condition: request.event_type_condition,
         event_version_condition: request.event_version_condition,
+        field_conditions: request.field_conditions,
+        payload_conditions: request.payload_conditions,
         description: request.description,
         group_metadata_field: request
             .group_metadata_field
             .map(|field| field.trim().to_string())
             .filter(|field| !field.is_empty()),
+        terminal: request.terminal.unwrap_or(true),
+        publisher: request
+            .publisher
+            .map(|publisher| publisher.trim().to_string())
+            .filter(|publisher| !publisher.is_empty()),
+        format: request.format,
+        partition_key: request.partition_key,
+        partition_metadata_field: request
+            .partition_metadata_field
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty()),
+        mqtt_topic_mapping: request.mqtt_topic_mapping,
+        stream_max_length: request.stream_max_length,
     };
     let result = service.update_routing_rule(id, &rule).await;
     match result {
@@ -356,6 +572,13 @@ async fn update_routing_rule(
             .header("Content-Type", "application/json")
             .body(Body::from(r#"{"status": "success"}"#))
             .unwrap()),
+        Err(crate::gateway::gateway::GatewayError::InvalidRule(err)) => Err(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "error": format!("Invalid routing rule: {err}") }).to_string(),
+            ))
+            .unwrap()),
         Err(err) => {
             error!("Failed to update routing rule {id}: {err}");
             Ok(Response::builder()
@@ -483,7 +706,7 @@ mod tests {
     use super::*;
     use crate::gateway::gateway::EventGateway;
     use crate::publisher::publisher::NoOpPublisher;
-    use crate::store::storage::InMemoryStorage;
+    use crate::store::storage::{InMemoryStorage, Storage};
     use axum::http::Request;
     use tower::ServiceExt;
 
@@ -501,6 +724,7 @@ mod tests {
 
         for path in [
             "/api/v1/health-check",
+            "/api/v1/ready",
             "/api/v1/metrics",
             "/api/v1/routing-rules",
             "/api/v1/topic-validations",
@@ -524,4 +748,426 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
+
+    #[tokio::test]
+    async fn batch_ingestion_reports_each_event() {
+        use crate::model::expressions::StringExpression;
+
+        let storage = InMemoryStorage::new();
+        storage
+            .add_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders").unwrap(),
+                event_type_condition: Condition::ONE(StringExpression::Equals {
+                    value: "order.created".to_string(),
+                }),
+                event_version_condition: None,
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                payload_conditions: Vec::new(),
+                field_conditions: Default::default(),
+            })
+            .await
+            .unwrap();
+        let service: Arc<GatewayService> = Arc::new(EventGateway::new(
+            Box::new(NoOpPublisher),
+            Box::new(storage),
+        ));
+        let app = build_router(service, "/api/v1", false, None);
+
+        let routed = Uuid::new_v4();
+        let unrouted = Uuid::new_v4();
+        let body = serde_json::json!([
+            {
+                "id": routed,
+                "eventType": "order.created",
+                "metadata": {},
+                "data": {"type": "json", "content": {}}
+            },
+            {
+                "id": unrouted,
+                "eventType": "order.deleted",
+                "metadata": {},
+                "data": {"type": "json", "content": {}}
+            }
+        ]);
+        let response = app
+            .oneshot(
+                Request::post("/api/v1/events")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let results: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            results,
+            serde_json::json!([
+                {
+                    "id": routed,
+                    "status": "published",
+                    "topics": [{"topic": "orders", "status": "published"}]
+                },
+                {"id": unrouted, "status": "no_route", "topics": []}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn fan_out_reports_partial_failures() {
+        use crate::model::expressions::StringExpression;
+
+        let storage = InMemoryStorage::new();
+        for (order, topic) in [(0, "analytics.orders"), (1, "orders")] {
+            storage
+                .add_rule(&TopicRoutingRule {
+                    id: Uuid::new_v4(),
+                    order,
+                    topic: TopicTemplate::new(topic).unwrap(),
+                    event_type_condition: Condition::ONE(StringExpression::Equals {
+                        value: "order.created".to_string(),
+                    }),
+                    event_version_condition: None,
+                    description: None,
+                    group_metadata_field: None,
+                    terminal: false,
+                    publisher: None,
+                    format: None,
+                    partition_key: None,
+                    partition_metadata_field: None,
+                    mqtt_topic_mapping: None,
+                    stream_max_length: None,
+                    payload_conditions: Vec::new(),
+                    field_conditions: Default::default(),
+                })
+                .await
+                .unwrap();
+        }
+        storage
+            .add_topic_validation(
+                &serde_json::from_value(serde_json::json!({
+                    "id": Uuid::new_v4(),
+                    "topic": "orders",
+                    "schema": {
+                        "name": "order",
+                        "description": null,
+                        "event_type": "order.created",
+                        "event_version": null,
+                        "metadata": null,
+                        "schema": {
+                            "type": "json",
+                            "data": {"type": "object", "required": ["order_id"]}
+                        }
+                    }
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let service: Arc<GatewayService> = Arc::new(EventGateway::new(
+            Box::new(NoOpPublisher),
+            Box::new(storage),
+        ));
+        let app = build_router(service, "/api/v1", false, None);
+
+        let body = serde_json::json!({
+            "id": Uuid::new_v4(),
+            "eventType": "order.created",
+            "metadata": {},
+            "data": {"type": "json", "content": {}}
+        });
+        let response = app
+            .oneshot(
+                Request::post("/api/v1/event")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "status": "partial",
+                "topics": [
+                    {"topic": "analytics.orders", "status": "published"},
+                    {"topic": "orders", "status": "schema_invalid"}
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn renders_topic_templates() {
+        let storage = InMemoryStorage::new();
+        storage
+            .add_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders.{metadata.region}").unwrap(),
+                event_type_condition: Condition::ANY,
+                event_version_condition: None,
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                payload_conditions: Vec::new(),
+                field_conditions: Default::default(),
+            })
+            .await
+            .unwrap();
+        let service: Arc<GatewayService> = Arc::new(EventGateway::new(
+            Box::new(NoOpPublisher),
+            Box::new(storage),
+        ));
+        let app = build_router(service, "/api/v1", false, None);
+
+        for (metadata, status, body) in [
+            (
+                serde_json::json!({"region": "eu"}),
+                StatusCode::OK,
+                serde_json::json!({
+                    "status": "success",
+                    "topics": [{"topic": "orders.eu", "status": "published"}]
+                }),
+            ),
+            (
+                serde_json::json!({}),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                serde_json::json!({"error": "topic rendering failed"}),
+            ),
+            (
+                serde_json::json!({"region": "eu west"}),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                serde_json::json!({"error": "topic rendering failed"}),
+            ),
+        ] {
+            let event = serde_json::json!({
+                "id": Uuid::new_v4(),
+                "eventType": "order.created",
+                "metadata": metadata,
+                "data": {"type": "json", "content": {}}
+            });
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::post("/api/v1/event")
+                        .header("content-type", "application/json")
+                        .body(Body::from(event.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let result: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(result, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn schema_failures_are_problem_details() {
+        let storage = InMemoryStorage::new();
+        storage
+            .add_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders").unwrap(),
+                event_type_condition: Condition::ANY,
+                event_version_condition: None,
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                payload_conditions: Vec::new(),
+                field_conditions: Default::default(),
+            })
+            .await
+            .unwrap();
+        for (name, required) in [("order", "order_id"), ("customer", "customer_id")] {
+            storage
+                .add_topic_validation(
+                    &serde_json::from_value(serde_json::json!({
+                        "id": Uuid::new_v4(),
+                        "topic": "orders",
+                        "schema": {
+                            "name": name,
+                            "description": null,
+                            "event_type": "order.created",
+                            "event_version": null,
+                            "metadata": null,
+                            "schema": {
+                                "type": "json",
+                                "data": {"type": "object", "required": [required]}
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+        let service: Arc<GatewayService> = Arc::new(EventGateway::new(
+            Box::new(NoOpPublisher),
+            Box::new(storage),
+        ));
+        let app = build_router(service, "/api/v1", false, None);
+
+        let id = Uuid::new_v4();
+        let body = serde_json::json!({
+            "id": id,
+            "eventType": "order.created",
+            "metadata": {},
+            "data": {"type": "json", "content": {}}
+        });
+        let response = app
+            .oneshot(
+                Request::post("/api/v1/event")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/problem+json"
+        );
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["event_id"], id.to_string());
+        assert_eq!(problem["topic"], "orders");
+        let mut names: Vec<&str> = problem["schemas"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|schema| schema["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["customer", "order"]);
+        let error = &problem["schemas"][0]["errors"][0];
+        assert_eq!(error["instance_path"], "");
+        assert_eq!(error["schema_path"], "/required");
+        assert!(error["message"]
+            .as_str()
+            .unwrap()
+            .contains("is a required property"));
+    }
+
+    #[tokio::test]
+    async fn ingests_cloud_events() {
+        let storage = InMemoryStorage::new();
+        storage
+            .add_rule(&TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders").unwrap(),
+                event_type_condition: Condition::ONE(
+                    crate::model::expressions::StringExpression::Equals {
+                        value: "order.created".to_string(),
+                    },
+                ),
+                event_version_condition: None,
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                payload_conditions: Vec::new(),
+                field_conditions: Default::default(),
+            })
+            .await
+            .unwrap();
+        let service: Arc<GatewayService> = Arc::new(EventGateway::new(
+            Box::new(NoOpPublisher),
+            Box::new(storage),
+        ));
+        let app = build_router(service, "/api/v1", false, None);
+
+        let structured = serde_json::json!({
+            "specversion": "1.0",
+            "id": "order-1",
+            "source": "/orders",
+            "type": "order.created",
+            "data": {"order_id": "42"}
+        });
+        let requests = [
+            Request::post("/api/v1/event")
+                .header("content-type", "application/cloudevents+json")
+                .body(Body::from(structured.to_string()))
+                .unwrap(),
+            Request::post("/api/v1/event")
+                .header("content-type", "application/json")
+                .header("ce-specversion", "1.0")
+                .header("ce-id", "order-1")
+                .header("ce-source", "%2Forders")
+                .header("ce-type", "order.created")
+                .body(Body::from(r#"{"order_id": "42"}"#))
+                .unwrap(),
+            Request::post("/api/v1/events")
+                .header("content-type", "application/cloudevents-batch+json")
+                .body(Body::from(serde_json::json!([structured]).to_string()))
+                .unwrap(),
+        ];
+        for request in requests {
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(String::from_utf8_lossy(&bytes).contains(r#""status":"published""#));
+        }
+
+        let response = app
+            .oneshot(
+                Request::post("/api/v1/event")
+                    .header("content-type", "application/cloudevents+json")
+                    .body(Body::from(r#"{"specversion": "1.0", "id": "1"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -27,15 +27,23 @@ use publisher::kafka_publisher::KafkaPublisher;
 use publisher::publisher::{NoOpPublisher, Publisher};
 use store::{
     cached_postgres_storage::CachedPostgresStorage,
+    deduplication::{DeduplicationStore, InMemoryDeduplicationStore},
     file_storage::FileStorage,
+    postgres_deduplication::PostgresDeduplicationStore,
     postgres_storage::PostgresStorage,
     storage::{InMemoryStorage, Storage},
 };
 
+use crate::gateway::deduplicated::{
+    DeduplicatingEventGateway, DeduplicationBackend, DeduplicationConfig,
+};
 use crate::gateway::gateway::{EventGateway, GateWay};
 use crate::gateway::metered::MeteredEventGateway;
+use crate::publisher::amqp_publisher::AmqpPublisher;
 use crate::publisher::mqtt_publisher::MqttPublisher;
+use crate::publisher::nats_publisher::NatsPublisher;
 use crate::publisher::pgmq_publisher::PgmqPublisher;
+use crate::publisher::redis_streams_publisher::RedisStreamsPublisher;
 use crate::ui::static_handler;
 
 async fn load_storage(
@@ -69,12 +77,46 @@ async fn load_publisher(
 ) -> Result<Box<dyn Publisher<Event> + Send + Sync>, Box<dyn std::error::Error>> {
     Ok(match config {
         PublisherConfig::NoOp => Box::new(NoOpPublisher),
-        PublisherConfig::Kafka(kafka_config) => Box::new(KafkaPublisher::new(kafka_config)?),
-        PublisherConfig::Mqtt(mqtt_config) => Box::new(MqttPublisher::new(mqtt_config)),
+        PublisherConfig::Amqp(amqp_config) => Box::new(AmqpPublisher::new(*amqp_config).await?),
+        PublisherConfig::Kafka(kafka_config) => Box::new(KafkaPublisher::new(*kafka_config).await?),
+        PublisherConfig::Mqtt(mqtt_config) => Box::new(MqttPublisher::new(*mqtt_config)?),
+        PublisherConfig::Nats(nats_config) => Box::new(NatsPublisher::new(*nats_config).await?),
         PublisherConfig::Pgmq(pgmq_config) => Box::new(PgmqPublisher::new(pgmq_config).await?),
+        PublisherConfig::RedisStreams(redis_config) => {
+            Box::new(RedisStreamsPublisher::new(*redis_config).await?)
+        }
     })
 }
 
+fn load_deduplication_store(
+    config: &DeduplicationConfig,
+    database: &DatabaseConfig,
+) -> Result<Arc<dyn DeduplicationStore>, Box<dyn std::error::Error>> {
+    Ok(match (&config.backend, database) {
+        (DeduplicationBackend::InMemory, _) => Arc::new(InMemoryDeduplicationStore::new()),
+        (DeduplicationBackend::Postgres, DatabaseConfig::Postgres(postgres_config)) => {
+            Arc::new(PostgresDeduplicationStore::new(postgres_config)?)
+        }
+        (DeduplicationBackend::Postgres, _) => {
+            return Err("postgres deduplication requires a postgres database".into())
+        }
+    })
+}
+
+/// Wraps `gateway` with deduplication when it is configured.
+fn deduplicated<T: GateWay + 'static>(
+    gateway: T,
+    deduplication: Option<(Arc<dyn DeduplicationStore>, DeduplicationConfig)>,
+) -> Arc<dyn GateWay + Send + Sync> {
+    match deduplication {
+        Some((store, config)) => {
+            info!("Deduplicating events for {} seconds", config.ttl_secs);
+            Arc::new(DeduplicatingEventGateway::new(gateway, store, &config))
+        }
+        None => Arc::new(gateway),
+    }
+}
+
 fn load_configuration() -> Result<AppConfig, config::ConfigError> {
     let config_path = std::env::var("APP_CONFIG_PATH").unwrap_or_else(|_| "config".to_string());
     info!("Loading config from {config_path}");
@@ -101,8 +143,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Loaded config: {app_config}");
     let storage = load_storage(app_config.database.clone()).await?;
     let publisher = load_publisher(app_config.gateway.publisher.clone()).await?;
-    let base_gateway = EventGateway::new(publisher, storage);
+    let mut base_gateway = EventGateway::new(publisher, storage);
+    for (name, config) in app_config.gateway.publishers.clone() {
+        info!("Loading publisher {name}");
+        base_gateway = base_gateway.with_publisher(name, load_publisher(config).await?);
+    }
+    if let Some(dead_letter) = app_config.gateway.dead_letter.clone() {
+        info!("Dead-lettering events to {}", dead_letter.topic);
+        base_gateway = base_gateway.with_dead_letter(dead_letter)?;
+    }
 
+    let deduplication = match app_config.gateway.deduplication.clone() {
+        Some(config) => Some((
+            load_deduplication_store(&config, &app_config.database)?,
+            config,
+        )),
+        None => None,
+    };
     let service: Arc<dyn GateWay + Send + Sync> = if app_config.gateway.metrics_enabled {
         info!("Metrics enabled - creating MeteredEventGateway");
         let metered_gateway = MeteredEventGateway::new(base_gateway).map_err(|e| {
@@ -110,9 +167,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             e
         })?;
         info!("Metrics registered successfully");
-        Arc::new(metered_gateway)
+        deduplicated(metered_gateway, deduplication)
     } else {
-        Arc::new(base_gateway)
+        deduplicated(base_gateway, deduplication)
     };
     info!("Loaded Gateway");
 
@@ -0,0 +1,363 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::model::event::{Data, DataType, Event, IngestContext};
+
+/// Content type of a single event in CloudEvents structured mode.
+pub const STRUCTURED_CONTENT_TYPE: &str = "application/cloudevents+json";
+/// Content type of a JSON array of structured CloudEvents.
+pub const BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
+
+const SPEC_VERSION: &str = "1.0";
+
+#[derive(Debug)]
+pub enum CloudEventError {
+    Malformed(String),
+    MissingAttribute(&'static str),
+    InvalidAttribute(&'static str, String),
+    UnsupportedSpecVersion(String),
+    InvalidData(String),
+}
+
+impl fmt::Display for CloudEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudEventError::Malformed(msg) => write!(f, "malformed CloudEvent: {msg}"),
+            CloudEventError::MissingAttribute(name) => {
+                write!(f, "missing CloudEvent attribute '{name}'")
+            }
+            CloudEventError::InvalidAttribute(name, msg) => {
+                write!(f, "invalid CloudEvent attribute '{name}': {msg}")
+            }
+            CloudEventError::UnsupportedSpecVersion(version) => {
+                write!(f, "unsupported CloudEvents specversion '{version}'")
+            }
+            CloudEventError::InvalidData(msg) => write!(f, "invalid CloudEvent data: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for CloudEventError {}
+
+/// A CloudEvents 1.0 event in JSON format, as sent in structured mode.
+/// Attributes other than the ones below are extensions.
+#[derive(Debug, Deserialize)]
+pub struct CloudEvent {
+    pub specversion: String,
+    pub id: String,
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub datacontenttype: Option<String>,
+    pub dataschema: Option<String>,
+    pub subject: Option<String>,
+    pub time: Option<DateTime<Utc>>,
+    pub data: Option<Value>,
+    pub data_base64: Option<String>,
+    #[serde(flatten)]
+    pub extensions: HashMap<String, Value>,
+}
+
+/// Media type without parameters, lowercased.
+pub fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn is_json(media_type: &str) -> bool {
+    media_type == "application/json" || media_type == "text/json" || media_type.ends_with("+json")
+}
+
+fn is_text(media_type: &str) -> bool {
+    media_type.starts_with("text/")
+}
+
+/// Event data for a JSON value, with strings kept as text and any other
+/// non-object value kept as its JSON text.
+fn json_data(value: Value) -> Data {
+    match value {
+        Value::Object(fields) => Data::Json(fields.into_iter().collect()),
+        Value::String(text) => Data::String(text),
+        other => Data::String(other.to_string()),
+    }
+}
+
+impl CloudEvent {
+    pub fn from_json(json: &[u8]) -> Result<Self, CloudEventError> {
+        serde_json::from_slice(json).map_err(|e| CloudEventError::Malformed(e.to_string()))
+    }
+
+    pub fn batch_from_json(json: &[u8]) -> Result<Vec<Self>, CloudEventError> {
+        serde_json::from_slice(json).map_err(|e| CloudEventError::Malformed(e.to_string()))
+    }
+
+    /// Converts a binary-mode event: `attributes` are the `ce-` headers,
+    /// without the prefix and percent-decoded, and `body` is the event data,
+    /// interpreted according to `content_type`.
+    pub fn from_binary(
+        mut attributes: HashMap<String, String>,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Event, CloudEventError> {
+        let mut take = |name: &'static str| attributes.remove(name);
+        let specversion =
+            take("specversion").ok_or(CloudEventError::MissingAttribute("specversion"))?;
+        let id = take("id").ok_or(CloudEventError::MissingAttribute("id"))?;
+        let source = take("source").ok_or(CloudEventError::MissingAttribute("source"))?;
+        let event_type = take("type").ok_or(CloudEventError::MissingAttribute("type"))?;
+        let time = take("time")
+            .map(|time| {
+                DateTime::parse_from_rfc3339(&time)
+                    .map(|time| time.with_timezone(&Utc))
+                    .map_err(|e| CloudEventError::InvalidAttribute("time", e.to_string()))
+            })
+            .transpose()?;
+        let dataschema = take("dataschema");
+        let subject = take("subject");
+        let cloud_event = CloudEvent {
+            specversion,
+            id,
+            source,
+            event_type,
+            datacontenttype: content_type.map(str::to_string),
+            dataschema,
+            subject,
+            time,
+            data: None,
+            data_base64: None,
+            extensions: attributes
+                .into_iter()
+                .map(|(name, value)| (name, Value::String(value)))
+                .collect(),
+        };
+
+        let media_type = content_type.map(media_type).unwrap_or_default();
+        let data = if is_json(&media_type) {
+            let value = serde_json::from_slice(body)
+                .map_err(|e| CloudEventError::InvalidData(e.to_string()))?;
+            json_data(value)
+        } else if is_text(&media_type) {
+            Data::String(
+                String::from_utf8(body.to_vec())
+                    .map_err(|e| CloudEventError::InvalidData(e.to_string()))?,
+            )
+        } else {
+            Data::Binary(body.to_vec())
+        };
+        cloud_event.into_event_with(data)
+    }
+
+    /// Converts a structured-mode event. Data without `datacontenttype` is
+    /// JSON, as the JSON event format specifies.
+    pub fn into_event(mut self) -> Result<Event, CloudEventError> {
+        let media_type = self
+            .datacontenttype
+            .as_deref()
+            .map(media_type)
+            .unwrap_or_else(|| "application/json".to_string());
+        let data = match (self.data.take(), self.data_base64.take()) {
+            (Some(_), Some(_)) => {
+                return Err(CloudEventError::InvalidData(
+                    "both data and data_base64 are set".to_string(),
+                ))
+            }
+            (None, Some(encoded)) => Data::Binary(
+                STANDARD
+                    .decode(encoded)
+                    .map_err(|e| CloudEventError::InvalidAttribute("data_base64", e.to_string()))?,
+            ),
+            (Some(value), None) if is_json(&media_type) || is_text(&media_type) => json_data(value),
+            (Some(Value::String(text)), None) => Data::Binary(text.into_bytes()),
+            (Some(value), None) => Data::Binary(value.to_string().into_bytes()),
+            (None, None) => Data::String(String::new()),
+        };
+        self.into_event_with(data)
+    }
+
+    /// Maps the attributes: `type` to the event type, `dataschema`, or else
+    /// `specversion`, to the event version, `source` to the origin, `time` to
+    /// the timestamp, and `subject`, `datacontenttype` and extensions to
+    /// metadata. Ids that are not UUIDs are replaced by a name-based UUID of
+    /// `source` and `id`, so retries keep the same event id, and the original
+    /// id is kept in metadata.
+    fn into_event_with(self, data: Data) -> Result<Event, CloudEventError> {
+        if self.specversion != SPEC_VERSION {
+            return Err(CloudEventError::UnsupportedSpecVersion(self.specversion));
+        }
+        for (name, value) in [
+            ("id", &self.id),
+            ("source", &self.source),
+            ("type", &self.event_type),
+        ] {
+            if value.is_empty() {
+                return Err(CloudEventError::InvalidAttribute(
+                    name,
+                    "must not be empty".to_string(),
+                ));
+            }
+        }
+
+        let mut metadata: HashMap<String, String> = self
+            .extensions
+            .into_iter()
+            .map(|(name, value)| match value {
+                Value::String(text) => (name, text),
+                other => (name, other.to_string()),
+            })
+            .collect();
+        if let Some(subject) = self.subject {
+            metadata.insert("subject".to_string(), subject);
+        }
+        if let Some(content_type) = self.datacontenttype {
+            metadata.insert("datacontenttype".to_string(), content_type);
+        }
+        let id = match Uuid::parse_str(&self.id) {
+            Ok(id) => id,
+            Err(_) => {
+                let id = name_based_id(&self.source, &self.id);
+                metadata.insert("id".to_string(), self.id);
+                id
+            }
+        };
+
+        let data_type = match &data {
+            Data::Json(_) => DataType::Json,
+            Data::String(_) => DataType::String,
+            Data::Binary(_) => DataType::Binary,
+        };
+        Ok(Event {
+            id,
+            event_type: self.event_type,
+            event_version: Some(self.dataschema.unwrap_or(self.specversion)),
+            metadata,
+            transport_metadata: None,
+            data_type: Some(data_type),
+            data,
+            timestamp: self.time,
+            origin: Some(self.source),
+            ingest: IngestContext {
+                cloud_event: true,
+                ..Default::default()
+            },
+        })
+    }
+}
+
+/// Version 5 UUID of `source` and `id` in the URL namespace.
+fn name_based_id(source: &str, id: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, format!("{source}#{id}").as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn converts_structured_events() {
+        let cloud_event = CloudEvent::from_json(
+            json!({
+                "specversion": "1.0",
+                "id": "A234-1234-1234",
+                "source": "/orders",
+                "type": "order.created",
+                "time": "2024-03-20T12:00:00Z",
+                "dataschema": "https://example.com/order/v2",
+                "tenant": "acme",
+                "priority": 3,
+                "data": {"order_id": "42"}
+            })
+            .to_string()
+            .as_bytes(),
+        )
+        .unwrap();
+        let event = cloud_event.into_event().unwrap();
+
+        assert_eq!(event.event_type, "order.created");
+        assert_eq!(
+            event.event_version.as_deref(),
+            Some("https://example.com/order/v2")
+        );
+        assert_eq!(event.origin.as_deref(), Some("/orders"));
+        assert_eq!(
+            event.timestamp.unwrap().to_rfc3339(),
+            "2024-03-20T12:00:00+00:00"
+        );
+        assert_eq!(event.metadata["tenant"], "acme");
+        assert_eq!(event.metadata["priority"], "3");
+        assert_eq!(event.metadata["id"], "A234-1234-1234");
+        assert_eq!(
+            event.id,
+            "0b6b00dc-2c7a-5ed0-9f87-46ddec6aade9"
+                .parse::<Uuid>()
+                .unwrap()
+        );
+        assert_eq!(event.data, json_data(json!({"order_id": "42"})));
+
+        let binary = CloudEvent::from_json(
+            json!({
+                "specversion": "1.0",
+                "id": "11111111-1111-4111-8111-111111111111",
+                "source": "/files",
+                "type": "file.uploaded",
+                "datacontenttype": "application/octet-stream",
+                "data_base64": "AAEC"
+            })
+            .to_string()
+            .as_bytes(),
+        )
+        .unwrap()
+        .into_event()
+        .unwrap();
+        assert_eq!(
+            binary.id.to_string(),
+            "11111111-1111-4111-8111-111111111111"
+        );
+        assert_eq!(binary.event_version.as_deref(), Some("1.0"));
+        assert_eq!(binary.data, Data::Binary(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn converts_binary_events() {
+        let attributes = HashMap::from([
+            ("specversion".to_string(), "1.0".to_string()),
+            ("id".to_string(), "1".to_string()),
+            ("source".to_string(), "/orders".to_string()),
+            ("type".to_string(), "order.created".to_string()),
+            ("tenant".to_string(), "acme".to_string()),
+        ]);
+
+        let event = CloudEvent::from_binary(
+            attributes.clone(),
+            Some("application/json; charset=utf-8"),
+            br#"{"order_id": "42"}"#,
+        )
+        .unwrap();
+        assert_eq!(event.data, json_data(json!({"order_id": "42"})));
+        assert_eq!(event.data_type, Some(DataType::Json));
+        assert_eq!(event.metadata["tenant"], "acme");
+
+        let event =
+            CloudEvent::from_binary(attributes.clone(), Some("text/plain"), b"hello").unwrap();
+        assert_eq!(event.data, Data::String("hello".to_string()));
+
+        let event = CloudEvent::from_binary(attributes.clone(), None, &[0, 1]).unwrap();
+        assert_eq!(event.data, Data::Binary(vec![0, 1]));
+
+        let mut unsupported = attributes;
+        unsupported.insert("specversion".to_string(), "0.3".to_string());
+        assert!(matches!(
+            CloudEvent::from_binary(unsupported, None, &[]),
+            Err(CloudEventError::UnsupportedSpecVersion(_))
+        ));
+    }
+}
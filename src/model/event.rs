@@ -18,6 +18,20 @@ pub enum Data {
     Binary(Vec<u8>),
 }
 
+impl Data {
+    /// Resolves a JSON Pointer (RFC 6901) such as `/order/country` against
+    /// JSON data. String and binary data never resolve.
+    pub fn pointer(&self, pointer: &str) -> Option<&serde_json::Value> {
+        let Data::Json(fields) = self else {
+            return None;
+        };
+        let path = pointer.strip_prefix('/')?;
+        let (first, rest) = path.split_at(path.find('/').unwrap_or(path.len()));
+        let key = first.replace("~1", "/").replace("~0", "~");
+        fields.get(&key)?.pointer(rest)
+    }
+}
+
 #[derive(Clone, Serialize, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Event {
@@ -30,6 +44,21 @@ pub struct Event {
     pub data: Data,
     pub timestamp: Option<DateTime<Utc>>,
     pub origin: Option<String>,
+    /// Set by the gateway while ingesting; never read from or written to
+    /// event JSON, so producers cannot forge it.
+    #[serde(skip)]
+    pub ingest: IngestContext,
+}
+
+/// How the gateway received an event.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IngestContext {
+    /// The event was ingested as a CloudEvent, whose `id`, `subject` and
+    /// `datacontenttype` attributes were kept in metadata.
+    pub cloud_event: bool,
+    /// W3C trace context of the ingesting request, published only as the
+    /// Kafka `traceparent` header.
+    pub traceparent: Option<String>,
 }
 
 impl Event {
@@ -101,6 +130,7 @@ mod tests {
             data: Data::Json(data_json.as_object().unwrap().clone().into_iter().collect()),
             timestamp: Some(timestamp),
             origin: Some("example".to_string()),
+            ingest: Default::default(),
         };
 
         let serialized = serde_json::to_string(&event).expect("Failed to serialize");
@@ -113,4 +143,23 @@ mod tests {
         println!("{serialized}");
         assert_eq!(deserialized, event);
     }
+
+    #[test]
+    fn resolves_json_pointers() {
+        let data = Data::Json(
+            json!({"order": {"country": "DE", "lines": [{"sku": "a"}]}, "a/b": 1})
+                .as_object()
+                .unwrap()
+                .clone()
+                .into_iter()
+                .collect(),
+        );
+
+        assert_eq!(data.pointer("/order/country"), Some(&json!("DE")));
+        assert_eq!(data.pointer("/order/lines/0/sku"), Some(&json!("a")));
+        assert_eq!(data.pointer("/a~1b"), Some(&json!(1)));
+        assert_eq!(data.pointer("/order/missing"), None);
+        assert_eq!(data.pointer("order"), None);
+        assert_eq!(Data::String("{}".to_string()).pointer("/order"), None);
+    }
 }
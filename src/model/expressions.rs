@@ -1,5 +1,9 @@
 use regex::Regex;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::event::Data;
 
 #[derive(Clone, Serialize, Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -23,6 +27,17 @@ pub enum StringExpression {
     Contains {
         value: String,
     },
+    SemverRange {
+        #[serde(
+            serialize_with = "version_req_serialize",
+            deserialize_with = "version_req_deserialize"
+        )]
+        value: VersionReq,
+    },
+    SemverMajor {
+        #[serde(deserialize_with = "major_deserialize")]
+        value: u64,
+    },
 }
 
 impl PartialEq for StringExpression {
@@ -32,6 +47,14 @@ impl PartialEq for StringExpression {
                 StringExpression::RegexMatch { value: left },
                 StringExpression::RegexMatch { value: right },
             ) => left.as_str() == right.as_str(),
+            (
+                StringExpression::SemverRange { value: left },
+                StringExpression::SemverRange { value: right },
+            ) => left.to_string() == right.to_string(),
+            (
+                StringExpression::SemverMajor { value: left },
+                StringExpression::SemverMajor { value: right },
+            ) => left == right,
             (
                 StringExpression::Equals { value: left },
                 StringExpression::Equals { value: right },
@@ -65,6 +88,49 @@ pub enum Condition {
     ONE(StringExpression),
 }
 
+/// Comparison against a number or boolean taken from JSON event data.
+#[derive(Clone, Serialize, PartialEq, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ValueExpression {
+    NumberEquals { value: f64 },
+    GreaterThan { value: f64 },
+    GreaterThanOrEqual { value: f64 },
+    LessThan { value: f64 },
+    LessThanOrEqual { value: f64 },
+    BooleanEquals { value: bool },
+}
+
+/// Either a string [`Condition`], applied to JSON strings, or a
+/// [`ValueExpression`], applied to JSON numbers and booleans.
+#[derive(Clone, Serialize, PartialEq, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PayloadMatch {
+    Value(ValueExpression),
+    String(Condition),
+}
+
+/// Condition on the value found at a JSON Pointer in `Data::Json` event data.
+#[derive(Clone, Serialize, PartialEq, Debug, Deserialize)]
+pub struct PayloadCondition {
+    #[serde(deserialize_with = "pointer_deserialize")]
+    pub pointer: String,
+    pub condition: PayloadMatch,
+}
+
+pub(crate) fn pointer_deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    let pointer = String::deserialize(deserializer)?;
+    if !pointer.starts_with('/') {
+        return Err(D::Error::custom(format!(
+            "JSON pointer '{pointer}' must start with '/'"
+        )));
+    }
+    Ok(pointer)
+}
+
 fn regex_serialize<S>(regex: &Regex, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -82,6 +148,44 @@ where
         .map_err(D::Error::custom)
 }
 
+fn version_req_serialize<S>(req: &VersionReq, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_str(req)
+}
+
+fn version_req_deserialize<'de, D>(deserializer: D) -> Result<VersionReq, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    VersionReq::parse(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+/// Accepts a number or, as sent by the UI condition builder, a numeric string.
+fn major_deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Major {
+        Number(u64),
+        String(String),
+    }
+    match Major::deserialize(deserializer)? {
+        Major::Number(major) => Ok(major),
+        Major::String(major) => major.trim().parse().map_err(D::Error::custom),
+    }
+}
+
+/// Versions that are not valid semver never match.
+fn parse_version(to: &str) -> Option<Version> {
+    Version::parse(to).ok()
+}
+
 impl Condition {
     pub fn matches(&self, to: &str) -> bool {
         match self {
@@ -92,6 +196,12 @@ impl Condition {
                 StringExpression::StartsWith { value } => to.starts_with(value),
                 StringExpression::EndsWith { value } => to.ends_with(value),
                 StringExpression::Contains { value } => to.contains(value),
+                StringExpression::SemverRange { value } => {
+                    parse_version(to).is_some_and(|version| value.matches(&version))
+                }
+                StringExpression::SemverMajor { value } => {
+                    parse_version(to).is_some_and(|version| version.major == *value)
+                }
             },
             Condition::AND(conditions) => conditions.iter().all(|cond| cond.matches(to)),
             Condition::OR(conditions) => conditions.iter().any(|cond| cond.matches(to)),
@@ -100,6 +210,45 @@ impl Condition {
     }
 }
 
+impl ValueExpression {
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            ValueExpression::BooleanEquals { value: expected } => {
+                value.as_bool() == Some(*expected)
+            }
+            ValueExpression::NumberEquals { value: expected } => {
+                value.as_f64().is_some_and(|actual| actual == *expected)
+            }
+            ValueExpression::GreaterThan { value: bound } => {
+                value.as_f64().is_some_and(|actual| actual > *bound)
+            }
+            ValueExpression::GreaterThanOrEqual { value: bound } => {
+                value.as_f64().is_some_and(|actual| actual >= *bound)
+            }
+            ValueExpression::LessThan { value: bound } => {
+                value.as_f64().is_some_and(|actual| actual < *bound)
+            }
+            ValueExpression::LessThanOrEqual { value: bound } => {
+                value.as_f64().is_some_and(|actual| actual <= *bound)
+            }
+        }
+    }
+}
+
+impl PayloadCondition {
+    /// Non-JSON data, a missing pointer and a value of the wrong JSON type
+    /// never match.
+    pub fn matches(&self, data: &Data) -> bool {
+        match (&self.condition, data.pointer(&self.pointer)) {
+            (PayloadMatch::String(condition), Some(Value::String(value))) => {
+                condition.matches(value)
+            }
+            (PayloadMatch::Value(expression), Some(value)) => expression.matches(value),
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,4 +536,120 @@ mod tests {
             r#"{"and":[{"type":"regexMatch","value":"^test.*"},{"type":"equals","value":"test123"}]}"#
         );
     }
+
+    #[test]
+    fn payload_conditions_match_json_values() {
+        let data = Data::Json(
+            serde_json::json!({"order": {"country": "DE", "priority": 3, "express": true}})
+                .as_object()
+                .unwrap()
+                .clone()
+                .into_iter()
+                .collect(),
+        );
+        let condition = |pointer: &str, condition: serde_json::Value| -> PayloadCondition {
+            serde_json::from_value(serde_json::json!({"pointer": pointer, "condition": condition}))
+                .unwrap()
+        };
+
+        assert!(condition(
+            "/order/country",
+            serde_json::json!({"type": "equals", "value": "DE"})
+        )
+        .matches(&data));
+        assert!(condition(
+            "/order/country",
+            serde_json::json!({"not": {"type": "equals", "value": "FR"}})
+        )
+        .matches(&data));
+        assert!(condition(
+            "/order/priority",
+            serde_json::json!({"type": "greaterThan", "value": 2})
+        )
+        .matches(&data));
+        assert!(!condition(
+            "/order/priority",
+            serde_json::json!({"type": "lessThanOrEqual", "value": 2})
+        )
+        .matches(&data));
+        assert!(condition(
+            "/order/express",
+            serde_json::json!({"type": "booleanEquals", "value": true})
+        )
+        .matches(&data));
+
+        // Missing pointers, mismatched JSON types and non-JSON data never match.
+        assert!(!condition("/order/missing", serde_json::json!("any")).matches(&data));
+        assert!(!condition(
+            "/order/priority",
+            serde_json::json!({"type": "equals", "value": "3"})
+        )
+        .matches(&data));
+        assert!(!condition("/order/country", serde_json::json!("any"))
+            .matches(&Data::String("DE".to_string())));
+    }
+
+    #[test]
+    fn payload_condition_serde_round_trip() {
+        let condition = PayloadCondition {
+            pointer: "/order/priority".to_string(),
+            condition: PayloadMatch::Value(ValueExpression::GreaterThanOrEqual { value: 2.0 }),
+        };
+        let serialized = serde_json::to_string(&condition).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"pointer":"/order/priority","condition":{"type":"greaterThanOrEqual","value":2.0}}"#
+        );
+        let deserialized: PayloadCondition = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, condition);
+
+        assert!(serde_json::from_str::<PayloadCondition>(
+            r#"{"pointer":"order/priority","condition":"any"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_semver_range() {
+        let condition: Condition =
+            serde_json::from_str(r#"{"type":"semverRange","value":">=1.2, <2"}"#).unwrap();
+        assert!(condition.matches("1.2.0"));
+        assert!(condition.matches("1.9.3"));
+        assert!(!condition.matches("1.1.9"));
+        assert!(!condition.matches("2.0.0"));
+        assert!(!condition.matches("1.5"));
+        assert!(!condition.matches("latest"));
+
+        let serialized = serde_json::to_string(&condition).unwrap();
+        assert_eq!(serialized, r#"{"type":"semverRange","value":">=1.2, <2"}"#);
+        assert_eq!(
+            serde_json::from_str::<Condition>(&serialized).unwrap(),
+            condition
+        );
+        assert!(
+            serde_json::from_str::<Condition>(r#"{"type":"semverRange","value":"1.x.y"}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_semver_major() {
+        let condition: Condition =
+            serde_json::from_str(r#"{"type":"semverMajor","value":1}"#).unwrap();
+        assert!(condition.matches("1.0.0"));
+        assert!(condition.matches("1.4.2-beta.1"));
+        assert!(!condition.matches("2.0.0"));
+        assert!(!condition.matches("1"));
+
+        // The UI condition builder sends values as strings.
+        let from_ui: Condition =
+            serde_json::from_str(r#"{"type":"semverMajor","value":"1"}"#).unwrap();
+        assert_eq!(from_ui, condition);
+        assert_eq!(
+            serde_json::to_string(&condition).unwrap(),
+            r#"{"type":"semverMajor","value":1}"#
+        );
+        assert!(
+            serde_json::from_str::<Condition>(r#"{"type":"semverMajor","value":"v1"}"#).is_err()
+        );
+    }
 }
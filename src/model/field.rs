@@ -0,0 +1,161 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+use super::event::Event;
+
+/// A string attribute of an event that routing conditions and topic
+/// templates can address by name: `eventType`, `eventVersion`, `origin`,
+/// `metadata.<key>` or `transportMetadata.<key>`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EventField {
+    EventType,
+    EventVersion,
+    Origin,
+    Metadata(String),
+    TransportMetadata(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct EventFieldError(String);
+
+impl fmt::Display for EventFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown event field '{}', expected 'eventType', 'eventVersion', 'origin', 'metadata.<key>' or 'transportMetadata.<key>'",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for EventFieldError {}
+
+impl EventField {
+    /// Returns the value of this field on `event`, or `None` when the event
+    /// does not carry it.
+    pub fn resolve<'a>(&self, event: &'a Event) -> Option<&'a str> {
+        match self {
+            EventField::EventType => Some(&event.event_type),
+            EventField::EventVersion => event.event_version.as_deref(),
+            EventField::Origin => event.origin.as_deref(),
+            EventField::Metadata(key) => event.metadata.get(key).map(String::as_str),
+            EventField::TransportMetadata(key) => event
+                .transport_metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get(key))
+                .map(String::as_str),
+        }
+    }
+}
+
+impl FromStr for EventField {
+    type Err = EventFieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keyed = |prefix: &str| {
+            s.strip_prefix(prefix)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+        };
+        if s == "eventType" {
+            Ok(EventField::EventType)
+        } else if s == "eventVersion" {
+            Ok(EventField::EventVersion)
+        } else if s == "origin" {
+            Ok(EventField::Origin)
+        } else if let Some(key) = keyed("metadata.") {
+            Ok(EventField::Metadata(key))
+        } else if let Some(key) = keyed("transportMetadata.") {
+            Ok(EventField::TransportMetadata(key))
+        } else {
+            Err(EventFieldError(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for EventField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventField::EventType => write!(f, "eventType"),
+            EventField::EventVersion => write!(f, "eventVersion"),
+            EventField::Origin => write!(f, "origin"),
+            EventField::Metadata(key) => write!(f, "metadata.{key}"),
+            EventField::TransportMetadata(key) => write!(f, "transportMetadata.{key}"),
+        }
+    }
+}
+
+impl Serialize for EventField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EventField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::Data;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    #[test]
+    fn parses_and_displays_fields() {
+        for name in [
+            "eventType",
+            "eventVersion",
+            "origin",
+            "metadata.tenant_id",
+            "transportMetadata.jwt_sub",
+        ] {
+            assert_eq!(name.parse::<EventField>().unwrap().to_string(), name);
+        }
+        assert!("metadata.".parse::<EventField>().is_err());
+        assert!("eventId".parse::<EventField>().is_err());
+    }
+
+    #[test]
+    fn resolves_event_values() {
+        let event = Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            metadata: HashMap::from([("tenant_id".to_string(), "acme".to_string())]),
+            transport_metadata: Some(HashMap::from([(
+                "jwt_sub".to_string(),
+                "user-1".to_string(),
+            )])),
+            data_type: None,
+            data: Data::String("".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        };
+
+        assert_eq!(
+            EventField::Metadata("tenant_id".to_string()).resolve(&event),
+            Some("acme")
+        );
+        assert_eq!(
+            EventField::TransportMetadata("jwt_sub".to_string()).resolve(&event),
+            Some("user-1")
+        );
+        assert_eq!(EventField::EventType.resolve(&event), Some("order.created"));
+        assert_eq!(EventField::EventVersion.resolve(&event), None);
+        assert_eq!(EventField::Origin.resolve(&event), None);
+    }
+}
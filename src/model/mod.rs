@@ -1,4 +1,7 @@
+pub mod cloudevent;
 pub mod event;
 pub mod expressions;
+pub mod field;
+pub mod publishing;
 pub mod routing;
 pub mod topic;
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::event::Event;
+use super::expressions::pointer_deserialize;
+use super::topic::{Topic, TopicNaming, TopicTemplate, TopicTemplateError};
+
+/// How an event is written to the broker.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// The gateway's own `Event` JSON.
+    #[default]
+    Gateway,
+    /// A CloudEvents 1.0 JSON event.
+    CloudeventsStructured,
+    /// CloudEvents attributes as `ce-` headers and the event data as body.
+    CloudeventsBinary,
+    /// The event data only.
+    DataOnly,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Gateway => write!(f, "gateway"),
+            OutputFormat::CloudeventsStructured => write!(f, "cloudevents-structured"),
+            OutputFormat::CloudeventsBinary => write!(f, "cloudevents-binary"),
+            OutputFormat::DataOnly => write!(f, "data-only"),
+        }
+    }
+}
+
+/// Source of the Kafka record key. Keys from a missing field fall back to the
+/// event id.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PartitionKey {
+    EventId,
+    /// A metadata field.
+    Metadata {
+        field: String,
+    },
+    /// A value inside JSON event data, addressed by JSON Pointer; strings are
+    /// used as-is and other values as JSON.
+    Payload {
+        #[serde(deserialize_with = "pointer_deserialize")]
+        pointer: String,
+    },
+    /// Several metadata fields joined by `separator`.
+    Composite {
+        fields: Vec<String>,
+        #[serde(default = "default_separator")]
+        separator: String,
+    },
+    /// No key, so that the partitioner spreads records with sticky
+    /// partitioning.
+    Null,
+}
+
+fn default_separator() -> String {
+    ":".to_string()
+}
+
+impl PartitionKey {
+    /// Record key for `event`; `None` leaves the record without a key.
+    pub fn key(&self, event: &Event) -> Option<String> {
+        let key = match self {
+            PartitionKey::EventId => None,
+            PartitionKey::Metadata { field } => event.metadata.get(field).cloned(),
+            PartitionKey::Payload { pointer } => {
+                event.data.pointer(pointer).map(|value| match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                })
+            }
+            PartitionKey::Composite { fields, separator } => fields
+                .iter()
+                .map(|field| event.metadata.get(field).map(String::as_str))
+                .collect::<Option<Vec<&str>>>()
+                .filter(|values| !values.is_empty())
+                .map(|values| values.join(separator)),
+            PartitionKey::Null => return None,
+        };
+        Some(key.unwrap_or_else(|| event.id.to_string()))
+    }
+}
+
+/// How gateway topics become MQTT topics: `devices.telemetry` is published to
+/// `site/eu/devices/telemetry` with `dotsToSlashes` and the prefix
+/// `site/{metadata.region}/`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MqttTopicMapping {
+    /// Replaces the `.` of the gateway topic with MQTT level separators.
+    #[serde(default, alias = "dots_to_slashes")]
+    pub dots_to_slashes: bool,
+    /// Prepended to the topic; may contain `{field}` placeholders.
+    #[serde(default)]
+    pub prefix: Option<TopicTemplate>,
+}
+
+impl MqttTopicMapping {
+    /// MQTT topic of `event` published to the gateway `topic`.
+    pub fn apply(&self, topic: &str, event: &Event) -> Result<Topic, TopicTemplateError> {
+        let mut mapped = match &self.prefix {
+            Some(prefix) => prefix.render(event, TopicNaming::Mqtt)?.into_string(),
+            None => String::new(),
+        };
+        if self.dots_to_slashes {
+            mapped.push_str(&topic.replace('.', "/"));
+        } else {
+            mapped.push_str(topic);
+        }
+        TopicNaming::Mqtt
+            .topic(mapped)
+            .map_err(TopicTemplateError::InvalidTopic)
+    }
+}
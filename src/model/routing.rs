@@ -1,6 +1,15 @@
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    num::NonZeroU64,
+};
 
-use super::{expressions::Condition, topic::Topic};
+use super::{
+    expressions::{Condition, PayloadCondition},
+    field::EventField,
+    publishing::{MqttTopicMapping, OutputFormat, PartitionKey},
+    topic::{Topic, TopicTemplate},
+};
 use jsonschema::{Draft, Validator};
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
@@ -12,7 +21,7 @@ pub enum Schema {
     Json(JSchema),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ValidationError {
     pub message: String,
     pub instance_path: String,
@@ -151,16 +160,55 @@ pub struct TopicValidationConfig {
 pub struct TopicRoutingRule {
     pub id: Uuid,
     pub order: i32,
-    pub topic: Topic,
+    /// Destination topic, possibly with `{field}` placeholders.
+    pub topic: TopicTemplate,
     pub event_type_condition: Condition,
     pub event_version_condition: Option<Condition>,
+    /// Extra conditions on `origin`, `metadata.<key>` or
+    /// `transportMetadata.<key>`. All of them must match; an event without
+    /// the field does not match.
+    #[serde(default)]
+    pub field_conditions: BTreeMap<EventField, Condition>,
+    /// Conditions on values inside JSON event data, addressed by JSON
+    /// Pointer. All of them must match.
+    #[serde(default)]
+    pub payload_conditions: Vec<PayloadCondition>,
     pub description: Option<String>,
     pub group_metadata_field: Option<String>,
+    /// Stops routing after this rule matches. Non-terminal rules let the
+    /// event continue to later matching rules, publishing it to each topic.
+    #[serde(default = "default_terminal")]
+    pub terminal: bool,
+    /// Name of the configured publisher for this rule; `None` selects the
+    /// default publisher.
+    #[serde(default)]
+    pub publisher: Option<String>,
+    /// Output format for this rule; `None` keeps the publisher's format.
+    #[serde(default)]
+    pub format: Option<OutputFormat>,
+    /// Kafka record key for this rule; `None` keeps the publisher's key.
+    #[serde(default)]
+    pub partition_key: Option<PartitionKey>,
+    /// Metadata field holding the Kafka partition for this rule; `None`
+    /// keeps the publisher's.
+    #[serde(default)]
+    pub partition_metadata_field: Option<String>,
+    /// MQTT topic mapping for this rule; `None` keeps the publisher's.
+    #[serde(default)]
+    pub mqtt_topic_mapping: Option<MqttTopicMapping>,
+    /// Approximate maximum length of the Redis stream for this rule; `None`
+    /// keeps the publisher's.
+    #[serde(default)]
+    pub stream_max_length: Option<NonZeroU64>,
+}
+
+fn default_terminal() -> bool {
+    true
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::expressions::StringExpression;
+    use crate::model::expressions::{PayloadMatch, StringExpression};
 
     use super::*;
     use serde_json;
@@ -170,15 +218,40 @@ mod tests {
         let rule = TopicRoutingRule {
             id: Uuid::new_v4(),
             order: 1,
-            topic: Topic::new("example").unwrap(),
+            topic: TopicTemplate::new("example").unwrap(),
             event_type_condition: Condition::ONE(StringExpression::StartsWith {
                 value: "test".into(),
             }),
             event_version_condition: Some(Condition::ONE(StringExpression::Equals {
                 value: "1".into(),
             })),
+            field_conditions: BTreeMap::from([(
+                "metadata.tenant_id".parse().unwrap(),
+                Condition::ONE(StringExpression::Equals {
+                    value: "acme".into(),
+                }),
+            )]),
+            payload_conditions: vec![PayloadCondition {
+                pointer: "/order/country".into(),
+                condition: PayloadMatch::String(Condition::ONE(StringExpression::Equals {
+                    value: "DE".into(),
+                })),
+            }],
             description: Some("A routing rule.".into()),
             group_metadata_field: Some("aggregate_id".into()),
+            terminal: false,
+            publisher: None,
+            format: None,
+            partition_key: Some(PartitionKey::Composite {
+                fields: vec!["tenant_id".into(), "region".into()],
+                separator: "/".into(),
+            }),
+            partition_metadata_field: Some("partition".into()),
+            mqtt_topic_mapping: Some(MqttTopicMapping {
+                dots_to_slashes: true,
+                prefix: Some(TopicTemplate::new("site/{origin}/").unwrap()),
+            }),
+            stream_max_length: NonZeroU64::new(100_000),
         };
 
         let serialized = serde_json::to_string(&rule).unwrap();
@@ -202,6 +275,9 @@ mod tests {
         .unwrap();
 
         assert_eq!(rule.group_metadata_field, None);
+        assert!(rule.field_conditions.is_empty());
+        assert!(rule.payload_conditions.is_empty());
+        assert!(rule.terminal);
     }
 
     #[test]
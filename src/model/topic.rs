@@ -1,6 +1,9 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
+use super::event::Event;
+use super::field::{EventField, EventFieldError};
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Topic(String);
@@ -9,7 +12,10 @@ pub struct Topic(String);
 pub enum TopicValidationError {
     Empty,
     InvalidCharacters(String),
-    TooLong(usize),
+    /// Length of the topic and the broker's maximum.
+    TooLong(usize, usize),
+    /// A name the broker refuses for another reason.
+    Invalid(String),
 }
 
 impl fmt::Display for TopicValidationError {
@@ -19,9 +25,10 @@ impl fmt::Display for TopicValidationError {
             TopicValidationError::InvalidCharacters(chars) => {
                 write!(f, "Topic contains invalid characters: {chars}")
             }
-            TopicValidationError::TooLong(len) => {
-                write!(f, "Topic is too long: {len} characters (max: 255)")
+            TopicValidationError::TooLong(len, max) => {
+                write!(f, "Topic is too long: {len} characters (max: {max})")
             }
+            TopicValidationError::Invalid(message) => write!(f, "{message}"),
         }
     }
 }
@@ -34,43 +41,149 @@ impl Topic {
     /// - Not be empty
     /// - Contain only alphanumeric characters, dots, hyphens, and underscores
     /// - Be no longer than 255 characters
+    ///
+    /// Publishers narrow these rules with their [`TopicNaming`].
     pub fn new(s: impl Into<String>) -> Result<Self, TopicValidationError> {
+        TopicNaming::Generic.topic(s)
+    }
+
+    /// Get the inner string value
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert to String
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Topic naming rules of a broker.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TopicNaming {
+    /// Rules shared by every publisher.
+    #[default]
+    Generic,
+    /// Kafka topics: ASCII alphanumerics, `.`, `-` and `_`, at most 249
+    /// characters, and neither `.` nor `..`.
+    Kafka,
+    /// PGMQ queues: ASCII alphanumerics and `_`, not starting with a digit,
+    /// at most 47 characters so that the queue tables are valid PostgreSQL
+    /// identifiers.
+    Pgmq,
+    /// MQTT topics: generic names that may also contain `/`-separated
+    /// levels, without the `+` and `#` wildcards.
+    Mqtt,
+    /// NATS subjects: non-empty `.`-separated tokens of ASCII alphanumerics,
+    /// `-` and `_`, without the `*` and `>` wildcards.
+    Nats,
+}
+
+const GENERIC_MAX_LENGTH: usize = 255;
+const KAFKA_MAX_LENGTH: usize = 249;
+const PGMQ_MAX_LENGTH: usize = 47;
+
+impl TopicNaming {
+    /// Validates `s` as a topic name of this broker.
+    pub fn topic(self, s: impl Into<String>) -> Result<Topic, TopicValidationError> {
         let s = s.into();
 
         if s.is_empty() {
             return Err(TopicValidationError::Empty);
         }
 
-        if s.len() > 255 {
-            return Err(TopicValidationError::TooLong(s.len()));
+        let max = self.max_length();
+        if s.len() > max {
+            return Err(TopicValidationError::TooLong(s.len(), max));
         }
 
-        // Check for valid characters (alphanumeric, dots, hyphens, underscores)
-        let invalid_chars: Vec<char> = s
-            .chars()
-            .filter(|&c| !c.is_alphanumeric() && c != '.' && c != '-' && c != '_')
-            .collect();
+        self.check_characters(&s)?;
 
-        if !invalid_chars.is_empty() {
-            return Err(TopicValidationError::InvalidCharacters(
-                invalid_chars.into_iter().collect(),
-            ));
+        match self {
+            TopicNaming::Kafka if s == "." || s == ".." => Err(TopicValidationError::Invalid(
+                format!("Kafka topic cannot be '{s}'"),
+            )),
+            TopicNaming::Pgmq if starts_with_digit(&s) => Err(pgmq_leading_digit()),
+            TopicNaming::Nats if s.split('.').any(str::is_empty) => Err(
+                TopicValidationError::Invalid("NATS subject cannot have empty tokens".to_string()),
+            ),
+            _ => Ok(Topic(s)),
+        }
+    }
+
+    /// Validates a routing rule's topic template: the whole name when it has
+    /// no placeholders, else the characters of its literal parts.
+    pub fn check_template(self, template: &TopicTemplate) -> Result<(), TopicValidationError> {
+        if template.is_static() {
+            return self.topic(template.as_str()).map(|_| ());
+        }
+        for literal in template.literals() {
+            self.check_characters(literal)?;
+        }
+        match template.segments.first() {
+            Some(TemplateSegment::Literal(literal))
+                if self == TopicNaming::Pgmq && starts_with_digit(literal) =>
+            {
+                Err(pgmq_leading_digit())
+            }
+            _ => Ok(()),
         }
+    }
 
-        Ok(Topic(s))
+    /// Why the broker advises against `template`, if it does. Kafka metric
+    /// names replace `.` with `_`, so topics mixing both may collide.
+    pub fn warning(self, template: &TopicTemplate) -> Option<&'static str> {
+        let dot = template.literals().any(|literal| literal.contains('.'));
+        let underscore = template.literals().any(|literal| literal.contains('_'));
+        (self == TopicNaming::Kafka && dot && underscore)
+            .then_some("Kafka topics mixing '.' and '_' may collide in metric names")
     }
 
-    /// Get the inner string value
-    pub fn as_str(&self) -> &str {
-        &self.0
+    fn max_length(self) -> usize {
+        match self {
+            TopicNaming::Generic | TopicNaming::Mqtt | TopicNaming::Nats => GENERIC_MAX_LENGTH,
+            TopicNaming::Kafka => KAFKA_MAX_LENGTH,
+            TopicNaming::Pgmq => PGMQ_MAX_LENGTH,
+        }
     }
 
-    /// Convert to String
-    pub fn into_string(self) -> String {
-        self.0
+    fn check_characters(self, s: &str) -> Result<(), TopicValidationError> {
+        if self == TopicNaming::Mqtt && s.contains(['+', '#']) {
+            return Err(TopicValidationError::Invalid(
+                "MQTT topic cannot contain the wildcards '+' or '#'".to_string(),
+            ));
+        }
+        if self == TopicNaming::Nats && s.contains(['*', '>']) {
+            return Err(TopicValidationError::Invalid(
+                "NATS subject cannot contain the wildcards '*' or '>'".to_string(),
+            ));
+        }
+        let valid = |c: char| match self {
+            TopicNaming::Generic => c.is_alphanumeric() || matches!(c, '.' | '-' | '_'),
+            TopicNaming::Mqtt => c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | '/'),
+            TopicNaming::Kafka | TopicNaming::Nats => {
+                c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')
+            }
+            TopicNaming::Pgmq => c.is_ascii_alphanumeric() || c == '_',
+        };
+        let invalid_chars: String = s.chars().filter(|&c| !valid(c)).collect();
+
+        if invalid_chars.is_empty() {
+            Ok(())
+        } else {
+            Err(TopicValidationError::InvalidCharacters(invalid_chars))
+        }
     }
 }
 
+fn starts_with_digit(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn pgmq_leading_digit() -> TopicValidationError {
+    TopicValidationError::Invalid("PGMQ queue cannot start with a digit".to_string())
+}
+
 impl fmt::Display for Topic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -89,6 +202,164 @@ impl From<Topic> for String {
     }
 }
 
+/// Destination of a routing rule: a topic name that may contain `{field}`
+/// placeholders, such as `orders.{metadata.region}.{eventVersion}`, rendered
+/// for each event.
+#[derive(Clone, Debug)]
+pub struct TopicTemplate {
+    template: String,
+    segments: Vec<TemplateSegment>,
+}
+
+#[derive(Clone, Debug)]
+enum TemplateSegment {
+    Literal(String),
+    Field(EventField),
+}
+
+#[derive(Debug, Clone)]
+pub enum TopicTemplateError {
+    UnclosedPlaceholder,
+    UnexpectedBrace,
+    UnknownField(EventFieldError),
+    MissingField(EventField),
+    InvalidTopic(TopicValidationError),
+}
+
+impl fmt::Display for TopicTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopicTemplateError::UnclosedPlaceholder => {
+                write!(f, "Topic template has an unclosed '{{'")
+            }
+            TopicTemplateError::UnexpectedBrace => {
+                write!(f, "Topic template has an unexpected brace")
+            }
+            TopicTemplateError::UnknownField(err) => write!(f, "Topic template: {err}"),
+            TopicTemplateError::MissingField(field) => {
+                write!(f, "Event has no value for topic template field '{field}'")
+            }
+            TopicTemplateError::InvalidTopic(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for TopicTemplateError {}
+
+impl TopicTemplate {
+    /// Parses a template. Literal parts must only contain characters that some
+    /// broker accepts, and a template without placeholders must be a topic
+    /// name of the most permissive naming, [`TopicNaming::Mqtt`]. The
+    /// publisher's [`TopicNaming::check_template`] narrows these rules.
+    pub fn new(s: impl Into<String>) -> Result<Self, TopicTemplateError> {
+        let template = s.into();
+        let mut segments = Vec::new();
+        let mut rest = template.as_str();
+        while !rest.is_empty() {
+            match rest.find(['{', '}']) {
+                Some(start) if rest[start..].starts_with('}') => {
+                    return Err(TopicTemplateError::UnexpectedBrace)
+                }
+                Some(start) => {
+                    let end = rest[start..]
+                        .find('}')
+                        .ok_or(TopicTemplateError::UnclosedPlaceholder)?
+                        + start;
+                    let name = &rest[start + 1..end];
+                    if name.contains('{') {
+                        return Err(TopicTemplateError::UnexpectedBrace);
+                    }
+                    if start > 0 {
+                        segments.push(TemplateSegment::Literal(rest[..start].to_string()));
+                    }
+                    let field = name.parse().map_err(TopicTemplateError::UnknownField)?;
+                    segments.push(TemplateSegment::Field(field));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    segments.push(TemplateSegment::Literal(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+
+        let template = TopicTemplate { template, segments };
+        TopicNaming::Mqtt
+            .check_template(&template)
+            .map_err(TopicTemplateError::InvalidTopic)?;
+
+        Ok(template)
+    }
+
+    fn is_static(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, TemplateSegment::Literal(_)))
+    }
+
+    fn literals(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            TemplateSegment::Literal(literal) => Some(literal.as_str()),
+            TemplateSegment::Field(_) => None,
+        })
+    }
+
+    /// Substitutes the event's field values and validates the result as a
+    /// topic of `naming`.
+    pub fn render(&self, event: &Event, naming: TopicNaming) -> Result<Topic, TopicTemplateError> {
+        let mut topic = String::with_capacity(self.template.len());
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Literal(literal) => topic.push_str(literal),
+                TemplateSegment::Field(field) => topic.push_str(
+                    field
+                        .resolve(event)
+                        .ok_or_else(|| TopicTemplateError::MissingField(field.clone()))?,
+                ),
+            }
+        }
+        naming
+            .topic(topic)
+            .map_err(TopicTemplateError::InvalidTopic)
+    }
+
+    /// Get the template as written
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+}
+
+impl PartialEq for TopicTemplate {
+    fn eq(&self, other: &Self) -> bool {
+        self.template == other.template
+    }
+}
+
+impl fmt::Display for TopicTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.template)
+    }
+}
+
+impl Serialize for TopicTemplate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.template)
+    }
+}
+
+impl<'de> Deserialize<'de> for TopicTemplate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        TopicTemplate::new(String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,15 +381,117 @@ mod tests {
             Err(TopicValidationError::InvalidCharacters(_))
         ));
         assert!(matches!(
-            Topic::new("invalid/topic"),
+            Topic::new("invalid+topic"),
+            Err(TopicValidationError::InvalidCharacters(_))
+        ));
+        assert!(matches!(
+            Topic::new("orders/eu/created"),
             Err(TopicValidationError::InvalidCharacters(_))
         ));
         assert!(matches!(
             Topic::new("a".repeat(256)),
-            Err(TopicValidationError::TooLong(_))
+            Err(TopicValidationError::TooLong(256, 255))
         ));
     }
 
+    #[test]
+    fn test_broker_topic_naming() {
+        assert!(TopicNaming::Kafka.topic("orders.created-v1").is_ok());
+        assert!(matches!(
+            TopicNaming::Kafka.topic("orders/created"),
+            Err(TopicValidationError::InvalidCharacters(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Kafka.topic("commandes.créées"),
+            Err(TopicValidationError::InvalidCharacters(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Kafka.topic("a".repeat(250)),
+            Err(TopicValidationError::TooLong(250, 249))
+        ));
+        assert!(matches!(
+            TopicNaming::Kafka.topic(".."),
+            Err(TopicValidationError::Invalid(_))
+        ));
+
+        assert!(TopicNaming::Pgmq.topic("orders_created").is_ok());
+        assert!(matches!(
+            TopicNaming::Pgmq.topic("orders.created"),
+            Err(TopicValidationError::InvalidCharacters(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Pgmq.topic("1orders"),
+            Err(TopicValidationError::Invalid(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Pgmq.topic("q".repeat(48)),
+            Err(TopicValidationError::TooLong(48, 47))
+        ));
+
+        assert!(TopicNaming::Mqtt.topic("orders/eu/created").is_ok());
+        assert!(matches!(
+            TopicNaming::Mqtt.topic("orders/+/created"),
+            Err(TopicValidationError::Invalid(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Mqtt.topic("orders/#"),
+            Err(TopicValidationError::Invalid(_))
+        ));
+
+        assert!(TopicNaming::Nats.topic("orders.eu.created").is_ok());
+        assert!(matches!(
+            TopicNaming::Nats.topic("orders.*"),
+            Err(TopicValidationError::Invalid(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Nats.topic("orders..created"),
+            Err(TopicValidationError::Invalid(_))
+        ));
+        assert!(matches!(
+            TopicNaming::Nats.topic("orders/created"),
+            Err(TopicValidationError::InvalidCharacters(_))
+        ));
+    }
+
+    #[test]
+    fn test_broker_topic_templates() {
+        let template = TopicTemplate::new("orders/{metadata.region}").unwrap();
+        assert!(TopicNaming::Mqtt.check_template(&template).is_ok());
+        assert!(TopicNaming::Kafka.check_template(&template).is_err());
+        assert!(TopicNaming::Pgmq
+            .check_template(&TopicTemplate::new("1_{eventType}").unwrap())
+            .is_err());
+        assert!(TopicNaming::Pgmq
+            .check_template(&TopicTemplate::new("q".repeat(48)).unwrap())
+            .is_err());
+
+        assert!(matches!(
+            TopicTemplate::new("orders.{metadata.region}")
+                .unwrap()
+                .render(&event(), TopicNaming::Pgmq),
+            Err(TopicTemplateError::InvalidTopic(
+                TopicValidationError::InvalidCharacters(_)
+            ))
+        ));
+        assert_eq!(
+            TopicTemplate::new("orders/{metadata.path}")
+                .unwrap()
+                .render(&event(), TopicNaming::Mqtt)
+                .unwrap(),
+            TopicNaming::Mqtt.topic("orders/a/b").unwrap()
+        );
+
+        assert!(TopicNaming::Kafka
+            .warning(&TopicTemplate::new("orders.eu_west").unwrap())
+            .is_some());
+        assert!(TopicNaming::Kafka
+            .warning(&TopicTemplate::new("orders.{metadata.region}").unwrap())
+            .is_none());
+        assert!(TopicNaming::Mqtt
+            .warning(&TopicTemplate::new("orders.eu_west").unwrap())
+            .is_none());
+    }
+
     #[test]
     fn test_serialization() {
         let topic = Topic::new("test_topic").unwrap();
@@ -128,4 +501,102 @@ mod tests {
         let deserialized: Topic = serde_json::from_str(&serialized).unwrap();
         assert_eq!(topic, deserialized);
     }
+
+    fn event() -> Event {
+        Event {
+            id: uuid::Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: Some("1.2.0".to_string()),
+            metadata: std::collections::HashMap::from([
+                ("region".to_string(), "eu-west".to_string()),
+                ("path".to_string(), "a/b".to_string()),
+            ]),
+            transport_metadata: None,
+            data_type: None,
+            data: crate::model::event::Data::String("".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_topic_template_render() {
+        let template = TopicTemplate::new("orders.{metadata.region}.{eventVersion}").unwrap();
+        assert_eq!(
+            template.render(&event(), TopicNaming::Generic).unwrap(),
+            Topic::new("orders.eu-west.1.2.0").unwrap()
+        );
+        assert_eq!(
+            TopicTemplate::new("{eventType}")
+                .unwrap()
+                .render(&event(), TopicNaming::Generic)
+                .unwrap(),
+            Topic::new("order.created").unwrap()
+        );
+        assert_eq!(
+            TopicTemplate::new("static_topic")
+                .unwrap()
+                .render(&event(), TopicNaming::Generic)
+                .unwrap(),
+            Topic::new("static_topic").unwrap()
+        );
+
+        assert!(matches!(
+            TopicTemplate::new("orders.{origin}")
+                .unwrap()
+                .render(&event(), TopicNaming::Generic),
+            Err(TopicTemplateError::MissingField(EventField::Origin))
+        ));
+        assert!(matches!(
+            TopicTemplate::new("orders.{metadata.path}")
+                .unwrap()
+                .render(&event(), TopicNaming::Kafka),
+            Err(TopicTemplateError::InvalidTopic(
+                TopicValidationError::InvalidCharacters(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_topic_templates() {
+        assert!(matches!(
+            TopicTemplate::new("orders.{metadata.region"),
+            Err(TopicTemplateError::UnclosedPlaceholder)
+        ));
+        assert!(matches!(
+            TopicTemplate::new("orders.}"),
+            Err(TopicTemplateError::UnexpectedBrace)
+        ));
+        assert!(matches!(
+            TopicTemplate::new("orders.{{eventType}}"),
+            Err(TopicTemplateError::UnexpectedBrace)
+        ));
+        assert!(matches!(
+            TopicTemplate::new("orders.{region}"),
+            Err(TopicTemplateError::UnknownField(_))
+        ));
+        assert!(matches!(
+            TopicTemplate::new("orders {eventType}"),
+            Err(TopicTemplateError::InvalidTopic(_))
+        ));
+        assert!(matches!(
+            TopicTemplate::new(""),
+            Err(TopicTemplateError::InvalidTopic(
+                TopicValidationError::Empty
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_topic_template_serialization() {
+        let template = TopicTemplate::new("orders.{metadata.region}").unwrap();
+        let serialized = serde_json::to_string(&template).unwrap();
+        assert_eq!(serialized, "\"orders.{metadata.region}\"");
+        assert_eq!(
+            serde_json::from_str::<TopicTemplate>(&serialized).unwrap(),
+            template
+        );
+        assert!(serde_json::from_str::<TopicTemplate>("\"orders.{\"").is_err());
+    }
 }
@@ -0,0 +1,418 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::model::event::{Data, Event};
+use crate::model::publishing::OutputFormat;
+
+use super::publisher::{PublishContext, PublisherError};
+
+/// Output settings shared by every publisher.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EncodingConfig {
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Transport metadata keys kept in published messages; none of them
+    /// when unset.
+    #[serde(default)]
+    pub transport_metadata: Vec<String>,
+}
+
+/// Message body, kept typed so that JSON-based brokers can store it natively.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Payload {
+    Json(Value),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Payload {
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            Payload::Json(value) => value.to_string().into_bytes(),
+            Payload::Text(text) => text.into_bytes(),
+            Payload::Bytes(bytes) => bytes,
+        }
+    }
+
+    /// JSON value of the body; bytes become a base64 string.
+    pub fn into_json(self) -> Value {
+        match self {
+            Payload::Json(value) => value,
+            Payload::Text(text) => Value::String(text),
+            Payload::Bytes(bytes) => Value::String(STANDARD.encode(bytes)),
+        }
+    }
+}
+
+/// An encoded event: message headers, empty unless the format uses them, and
+/// the body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodedMessage {
+    pub headers: Vec<(String, String)>,
+    pub payload: Payload,
+}
+
+impl EncodedMessage {
+    /// Media type of the body: the `content-type` header of binary mode, else
+    /// derived from the format and payload.
+    pub fn content_type(&self, format: OutputFormat) -> String {
+        if let Some((_, value)) = self.headers.iter().find(|(name, _)| name == "content-type") {
+            return value.clone();
+        }
+        match &self.payload {
+            _ if format == OutputFormat::CloudeventsStructured => "application/cloudevents+json",
+            Payload::Json(_) => "application/json",
+            Payload::Text(_) => "text/plain",
+            Payload::Bytes(_) => "application/octet-stream",
+        }
+        .to_string()
+    }
+}
+
+/// Metadata keys that carry CloudEvents attributes of ingested CloudEvents
+/// rather than extensions.
+const ATTRIBUTE_METADATA: [&str; 3] = ["id", "subject", "datacontenttype"];
+
+const RESERVED_ATTRIBUTES: [&str; 11] = [
+    "specversion",
+    "id",
+    "source",
+    "type",
+    "subject",
+    "time",
+    "datacontenttype",
+    "dataschema",
+    "data",
+    "data_base64",
+    "eventversion",
+];
+
+const DEFAULT_SOURCE: &str = "event-gateway";
+
+/// Prefix of the failure details the gateway adds to dead-lettered events,
+/// which are kept without being listed.
+const DEAD_LETTER_PREFIX: &str = "dead_letter_";
+
+pub struct Encoder {
+    format: OutputFormat,
+    transport_metadata: Vec<String>,
+}
+
+impl Encoder {
+    pub fn new(config: EncodingConfig) -> Self {
+        Encoder {
+            format: config.format,
+            transport_metadata: config.transport_metadata,
+        }
+    }
+
+    /// Format of a message: the rule's override, else the publisher's.
+    pub fn format(&self, context: &PublishContext) -> OutputFormat {
+        context.format.unwrap_or(self.format)
+    }
+
+    pub fn encode(
+        &self,
+        event: &Event,
+        context: &PublishContext,
+    ) -> Result<EncodedMessage, PublisherError> {
+        let transport_metadata = self.transport_metadata(event);
+        let (headers, payload) = match self.format(context) {
+            OutputFormat::Gateway => {
+                let mut event = event.clone();
+                event.transport_metadata = transport_metadata;
+                let value = serde_json::to_value(&event)
+                    .map_err(|e| PublisherError::Generic(e.to_string()))?;
+                (Vec::new(), Payload::Json(value))
+            }
+            OutputFormat::CloudeventsStructured => {
+                let mut cloud_event = Map::new();
+                for (name, value) in attributes(event, transport_metadata.as_ref()) {
+                    cloud_event.insert(name, Value::String(value));
+                }
+                match data_payload(&event.data) {
+                    Payload::Json(value) => cloud_event.insert("data".to_string(), value),
+                    Payload::Text(text) => cloud_event.insert("data".to_string(), text.into()),
+                    Payload::Bytes(bytes) => {
+                        cloud_event.insert("data_base64".to_string(), STANDARD.encode(bytes).into())
+                    }
+                };
+                (Vec::new(), Payload::Json(Value::Object(cloud_event)))
+            }
+            OutputFormat::CloudeventsBinary => {
+                let mut headers = Vec::new();
+                for (name, value) in attributes(event, transport_metadata.as_ref()) {
+                    if name == "datacontenttype" {
+                        headers.push(("content-type".to_string(), value));
+                    } else {
+                        headers.push((format!("ce-{name}"), value));
+                    }
+                }
+                (headers, data_payload(&event.data))
+            }
+            OutputFormat::DataOnly => (Vec::new(), data_payload(&event.data)),
+        };
+        Ok(EncodedMessage { headers, payload })
+    }
+
+    /// Transport metadata of `event` that may leave the gateway, `None` when
+    /// no key is allowed.
+    pub fn transport_metadata(&self, event: &Event) -> Option<HashMap<String, String>> {
+        let metadata: HashMap<String, String> = event
+            .transport_metadata
+            .as_ref()?
+            .iter()
+            .filter(|(key, _)| {
+                self.transport_metadata.contains(key) || key.starts_with(DEAD_LETTER_PREFIX)
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        (!metadata.is_empty()).then_some(metadata)
+    }
+}
+
+fn data_payload(data: &Data) -> Payload {
+    match data {
+        Data::Json(fields) => Payload::Json(Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        )),
+        Data::String(text) => Payload::Text(text.clone()),
+        Data::Binary(bytes) => Payload::Bytes(bytes.clone()),
+    }
+}
+
+/// Lowercase alphanumeric extension name, as CloudEvents requires.
+fn extension_name(key: &str) -> Option<String> {
+    let name: String = key
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    (!name.is_empty() && !RESERVED_ATTRIBUTES.contains(&name.as_str())).then_some(name)
+}
+
+/// CloudEvents attributes of `event`. The attributes an ingested CloudEvent
+/// left in metadata are restored, the event version becomes the
+/// `eventversion` extension, and metadata and allowed transport metadata
+/// become extensions, metadata taking precedence. Metadata of other events
+/// never overrides attributes.
+fn attributes(
+    event: &Event,
+    transport_metadata: Option<&HashMap<String, String>>,
+) -> Vec<(String, String)> {
+    let content_type = match &event.data {
+        Data::Json(_) => "application/json",
+        Data::String(_) => "text/plain",
+        Data::Binary(_) => "application/octet-stream",
+    };
+    let metadata = |key: &str| {
+        event
+            .ingest
+            .cloud_event
+            .then(|| event.metadata.get(key).cloned())
+            .flatten()
+    };
+    let mut attributes = vec![
+        ("specversion".to_string(), "1.0".to_string()),
+        (
+            "id".to_string(),
+            metadata("id").unwrap_or_else(|| event.id.to_string()),
+        ),
+        (
+            "source".to_string(),
+            event
+                .origin
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SOURCE.to_string()),
+        ),
+        ("type".to_string(), event.event_type.clone()),
+        (
+            "datacontenttype".to_string(),
+            metadata("datacontenttype").unwrap_or_else(|| content_type.to_string()),
+        ),
+    ];
+    if let Some(subject) = metadata("subject") {
+        attributes.push(("subject".to_string(), subject));
+    }
+    if let Some(time) = event.timestamp {
+        attributes.push(("time".to_string(), time.to_rfc3339()));
+    }
+    if let Some(version) = &event.event_version {
+        attributes.push(("eventversion".to_string(), version.clone()));
+    }
+
+    let mut extensions: Vec<(String, String)> = Vec::new();
+    let mut add = |key: &String, value: &String| {
+        if let Some(name) = extension_name(key) {
+            if !extensions.iter().any(|(existing, _)| *existing == name) {
+                extensions.push((name, value.clone()));
+            }
+        }
+    };
+    let mut metadata: Vec<_> = event
+        .metadata
+        .iter()
+        .filter(|(key, _)| !ATTRIBUTE_METADATA.contains(&key.as_str()))
+        .collect();
+    metadata.sort();
+    metadata
+        .into_iter()
+        .for_each(|(key, value)| add(key, value));
+    let mut transport: Vec<_> = transport_metadata.into_iter().flatten().collect();
+    transport.sort();
+    transport
+        .into_iter()
+        .for_each(|(key, value)| add(key, value));
+    attributes.extend(extensions);
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn event() -> Event {
+        Event {
+            id: Uuid::parse_str("11111111-1111-4111-8111-111111111111").unwrap(),
+            event_type: "order.created".to_string(),
+            event_version: Some("2".to_string()),
+            metadata: HashMap::from([("tenant_id".to_string(), "acme".to_string())]),
+            transport_metadata: Some(HashMap::from([
+                ("originatorIp".to_string(), "10.0.0.1".to_string()),
+                ("jwt_sub".to_string(), "client".to_string()),
+            ])),
+            data_type: None,
+            data: Data::Json(HashMap::from([("order_id".to_string(), json!("42"))])),
+            timestamp: None,
+            origin: Some("/orders".to_string()),
+            ingest: Default::default(),
+        }
+    }
+
+    fn encoder(format: OutputFormat) -> Encoder {
+        Encoder::new(EncodingConfig {
+            format,
+            transport_metadata: vec!["jwt_sub".to_string()],
+        })
+    }
+
+    #[test]
+    fn filters_transport_metadata() {
+        let message = encoder(OutputFormat::Gateway)
+            .encode(&event(), &PublishContext::default())
+            .unwrap();
+        let Payload::Json(value) = message.payload else {
+            panic!("expected JSON payload");
+        };
+        assert_eq!(value["transportMetadata"], json!({"jwt_sub": "client"}));
+        assert!(message.headers.is_empty());
+
+        let encoder = Encoder::new(EncodingConfig::default());
+        let message = encoder
+            .encode(&event(), &PublishContext::default())
+            .unwrap();
+        let Payload::Json(value) = message.payload else {
+            panic!("expected JSON payload");
+        };
+        assert_eq!(value["transportMetadata"], Value::Null);
+
+        let mut dead_letter = event();
+        dead_letter
+            .transport_metadata
+            .get_or_insert_with(HashMap::new)
+            .insert("dead_letter_reason".to_string(), "no_route".to_string());
+        assert_eq!(
+            encoder.transport_metadata(&dead_letter),
+            Some(HashMap::from([(
+                "dead_letter_reason".to_string(),
+                "no_route".to_string()
+            )]))
+        );
+    }
+
+    #[test]
+    fn encodes_cloud_events() {
+        let structured = encoder(OutputFormat::CloudeventsStructured)
+            .encode(&event(), &PublishContext::default())
+            .unwrap();
+        assert_eq!(
+            structured.payload,
+            Payload::Json(json!({
+                "specversion": "1.0",
+                "id": "11111111-1111-4111-8111-111111111111",
+                "source": "/orders",
+                "type": "order.created",
+                "datacontenttype": "application/json",
+                "eventversion": "2",
+                "tenantid": "acme",
+                "jwtsub": "client",
+                "data": {"order_id": "42"}
+            }))
+        );
+
+        let context = PublishContext {
+            format: Some(OutputFormat::CloudeventsBinary),
+            ..PublishContext::default()
+        };
+        let binary = encoder(OutputFormat::Gateway)
+            .encode(&event(), &context)
+            .unwrap();
+        assert!(binary
+            .headers
+            .contains(&("ce-type".to_string(), "order.created".to_string())));
+        assert!(binary
+            .headers
+            .contains(&("content-type".to_string(), "application/json".to_string())));
+        assert!(!binary
+            .headers
+            .iter()
+            .any(|(name, _)| name == "ce-originatorip"));
+        assert_eq!(binary.payload, Payload::Json(json!({"order_id": "42"})));
+    }
+
+    #[test]
+    fn restores_attributes_of_ingested_cloud_events_only() {
+        let mut event = event();
+        event.metadata.extend([
+            ("id".to_string(), "A234-1234-1234".to_string()),
+            ("subject".to_string(), "order/42".to_string()),
+        ]);
+        let attribute = |event: &Event, name: &str| {
+            let message = encoder(OutputFormat::CloudeventsStructured)
+                .encode(event, &PublishContext::default())
+                .unwrap();
+            let Payload::Json(value) = message.payload else {
+                panic!("expected JSON payload");
+            };
+            value.get(name).cloned()
+        };
+
+        assert_eq!(
+            attribute(&event, "id"),
+            Some(json!("11111111-1111-4111-8111-111111111111"))
+        );
+        assert_eq!(attribute(&event, "subject"), None);
+
+        event.ingest.cloud_event = true;
+        assert_eq!(attribute(&event, "id"), Some(json!("A234-1234-1234")));
+        assert_eq!(attribute(&event, "subject"), Some(json!("order/42")));
+    }
+
+    #[test]
+    fn encodes_data_only() {
+        let mut event = event();
+        event.data = Data::Binary(vec![0, 1]);
+        let message = encoder(OutputFormat::DataOnly)
+            .encode(&event, &PublishContext::default())
+            .unwrap();
+        assert_eq!(message.payload, Payload::Bytes(vec![0, 1]));
+        assert_eq!(message.payload.into_json(), json!("AAE="));
+    }
+}
@@ -1,15 +1,23 @@
 use async_trait::async_trait;
 use duration_str::deserialize_duration;
+use futures::future::join_all;
+use log::error;
 use rdkafka::{
-    producer::{FutureProducer, FutureRecord},
+    error::KafkaResult,
+    message::{Header, OwnedHeaders},
+    producer::{FutureProducer, FutureRecord, Producer},
     ClientConfig,
 };
 use serde::Deserialize;
-use std::{fmt, time::Duration};
+use std::{collections::HashMap, fmt, time::Duration};
+use tokio::{sync::Mutex, task};
 
 use crate::model::event::Event;
+use crate::model::publishing::PartitionKey;
+use crate::model::topic::TopicNaming;
 
-use super::publisher::{PublishContext, Publisher, PublisherError};
+use super::format::{Encoder, EncodingConfig};
+use super::publisher::{PublishContext, PublishRequest, Publisher, PublisherError};
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -17,6 +25,8 @@ pub enum KCompression {
     None,
     Gzip,
     Snappy,
+    Lz4,
+    Zstd,
 }
 
 impl fmt::Display for KCompression {
@@ -25,11 +35,13 @@ impl fmt::Display for KCompression {
             KCompression::None => write!(f, "none"),
             KCompression::Gzip => write!(f, "gzip"),
             KCompression::Snappy => write!(f, "snappy"),
+            KCompression::Lz4 => write!(f, "lz4"),
+            KCompression::Zstd => write!(f, "zstd"),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum KRequiredAcks {
     None,
@@ -47,6 +59,161 @@ impl fmt::Display for KRequiredAcks {
     }
 }
 
+/// librdkafka partitioner for records without an explicit partition.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum KPartitioner {
+    /// Java client compatible hashing of the key.
+    Murmur2Random,
+    /// CRC32 hashing of the key, librdkafka's default.
+    ConsistentRandom,
+}
+
+impl fmt::Display for KPartitioner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KPartitioner::Murmur2Random => write!(f, "murmur2_random"),
+            KPartitioner::ConsistentRandom => write!(f, "consistent_random"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KSecurityProtocol {
+    Plaintext,
+    Ssl,
+    SaslPlaintext,
+    SaslSsl,
+}
+
+impl KSecurityProtocol {
+    fn uses_sasl(self) -> bool {
+        matches!(
+            self,
+            KSecurityProtocol::SaslPlaintext | KSecurityProtocol::SaslSsl
+        )
+    }
+
+    fn uses_ssl(self) -> bool {
+        matches!(self, KSecurityProtocol::Ssl | KSecurityProtocol::SaslSsl)
+    }
+}
+
+impl fmt::Display for KSecurityProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KSecurityProtocol::Plaintext => write!(f, "plaintext"),
+            KSecurityProtocol::Ssl => write!(f, "ssl"),
+            KSecurityProtocol::SaslPlaintext => write!(f, "sasl_plaintext"),
+            KSecurityProtocol::SaslSsl => write!(f, "sasl_ssl"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum KSaslMechanism {
+    Plain,
+    #[serde(rename = "scram-sha-256")]
+    ScramSha256,
+    #[serde(rename = "scram-sha-512")]
+    ScramSha512,
+}
+
+impl fmt::Display for KSaslMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KSaslMechanism::Plain => write!(f, "PLAIN"),
+            KSaslMechanism::ScramSha256 => write!(f, "SCRAM-SHA-256"),
+            KSaslMechanism::ScramSha512 => write!(f, "SCRAM-SHA-512"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaSecurityConfig {
+    protocol: KSecurityProtocol,
+    sasl_mechanism: Option<KSaslMechanism>,
+    sasl_username: Option<String>,
+    sasl_password: Option<String>,
+    ssl_ca_location: Option<String>,
+    ssl_certificate_location: Option<String>,
+    ssl_key_location: Option<String>,
+    ssl_key_password: Option<String>,
+}
+
+impl KafkaSecurityConfig {
+    /// librdkafka properties of these settings, rejecting combinations that
+    /// the protocol would ignore.
+    fn properties(&self) -> Result<Vec<(&'static str, String)>, PublisherError> {
+        let invalid = |message: &str| {
+            Err(PublisherError::Generic(format!(
+                "invalid Kafka security settings: {message}"
+            )))
+        };
+        let protocol = self.protocol;
+        let mut properties = vec![("security.protocol", protocol.to_string())];
+
+        let sasl = [
+            ("sasl.username", &self.sasl_username),
+            ("sasl.password", &self.sasl_password),
+        ];
+        if protocol.uses_sasl() {
+            let Some(mechanism) = self.sasl_mechanism else {
+                return invalid(&format!("protocol {protocol} requires sasl_mechanism"));
+            };
+            properties.push(("sasl.mechanism", mechanism.to_string()));
+            for (name, value) in sasl {
+                let Some(value) = value else {
+                    return invalid(&format!(
+                        "protocol {protocol} requires {}",
+                        name.replace('.', "_")
+                    ));
+                };
+                properties.push((name, value.clone()));
+            }
+        } else if self.sasl_mechanism.is_some() || sasl.iter().any(|(_, value)| value.is_some()) {
+            return invalid(&format!("protocol {protocol} does not use SASL settings"));
+        }
+
+        let ssl = [
+            ("ssl.ca.location", &self.ssl_ca_location),
+            ("ssl.certificate.location", &self.ssl_certificate_location),
+            ("ssl.key.location", &self.ssl_key_location),
+            ("ssl.key.password", &self.ssl_key_password),
+        ];
+        if !protocol.uses_ssl() && ssl.iter().any(|(_, value)| value.is_some()) {
+            return invalid(&format!("protocol {protocol} does not use SSL settings"));
+        }
+        if self.ssl_certificate_location.is_some() != self.ssl_key_location.is_some() {
+            return invalid("ssl_certificate_location and ssl_key_location must be set together");
+        }
+        for (name, value) in ssl {
+            if let Some(value) = value {
+                properties.push((name, value.clone()));
+            }
+        }
+        Ok(properties)
+    }
+}
+
+/// Properties set from typed settings, which `extra_properties` may not
+/// override, besides the ones of `security`.
+const TYPED_PROPERTIES: [&str; 10] = [
+    "bootstrap.servers",
+    "client.id",
+    "compression.type",
+    "acks",
+    "connections.max.idle.ms",
+    "message.timeout.ms",
+    "request.timeout.ms",
+    "enable.idempotence",
+    "transactional.id",
+    "partitioner",
+];
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct KafkaPublisherConfig {
     brokers: Vec<String>,
@@ -60,18 +227,108 @@ pub struct KafkaPublisherConfig {
     #[serde(deserialize_with = "deserialize_duration")]
     ack_timeout: Duration,
     metadata_field_as_key: Option<String>,
+    /// Record key of rules without their own `partitionKey`; replaces
+    /// `metadata_field_as_key`.
+    partition_key: Option<PartitionKey>,
+    /// Metadata field holding the record's partition number, for rules
+    /// without their own `partitionMetadataField`.
+    partition_metadata_field: Option<String>,
+    partitioner: Option<KPartitioner>,
+    /// Lets the broker discard duplicates caused by producer retries.
+    #[serde(default)]
+    enable_idempotence: bool,
+    /// Publishes each batch in a single transaction; implies idempotence.
+    transactional_id: Option<String>,
+    security: Option<Box<KafkaSecurityConfig>>,
+    /// librdkafka properties without a typed setting.
+    #[serde(default)]
+    extra_properties: HashMap<String, String>,
+    /// Metadata and transport metadata keys copied into record headers.
+    #[serde(default)]
+    header_metadata: Vec<String>,
+    #[serde(flatten)]
+    pub encoding: EncodingConfig,
 }
 
 pub struct KafkaPublisher {
     producer: FutureProducer,
-    metadata_field_as_key: Option<String>,
+    partition_key: PartitionKey,
+    partition_metadata_field: Option<String>,
+    header_metadata: Vec<String>,
+    encoder: Encoder,
+    /// Serializes transactions, since a producer runs one at a time. `None`
+    /// unless the producer is transactional.
+    transaction: Option<Mutex<TransactionState>>,
+    transaction_timeout: Duration,
+}
+
+/// Whether a transactional producer may start transactions. librdkafka cannot
+/// recover a producer after a fatal error or a failed abort.
+#[derive(Clone, Copy, PartialEq)]
+enum TransactionState {
+    Ready,
+    Failed,
+}
+
+/// W3C trace context, forwarded from metadata or the ingesting request.
+const TRACEPARENT: &str = "traceparent";
+
+/// Key, partition, value and headers of a Kafka record.
+struct Record {
+    key: Option<String>,
+    partition: Option<i32>,
+    value: Vec<u8>,
+    headers: OwnedHeaders,
 }
 
 impl KafkaPublisher {
-    pub fn new(cfg: KafkaPublisherConfig) -> Result<Self, PublisherError> {
-        let producer: FutureProducer = ClientConfig::new()
+    /// Creates the producer; a transactional producer also registers its
+    /// transactional id with the cluster, which must then be reachable.
+    pub async fn new(cfg: KafkaPublisherConfig) -> Result<Self, PublisherError> {
+        let client_config = Self::client_config(&cfg)?;
+        let partition_key = match (cfg.partition_key, cfg.metadata_field_as_key) {
+            (Some(_), Some(_)) => {
+                return Err(PublisherError::Generic(
+                    "Kafka partition_key and metadata_field_as_key cannot be set together"
+                        .to_string(),
+                ))
+            }
+            (Some(key), None) => key,
+            (None, Some(field)) => PartitionKey::Metadata { field },
+            (None, None) => PartitionKey::EventId,
+        };
+        let producer: FutureProducer = client_config.create().map_err(|error| {
+            PublisherError::Generic(format!("failed to create Kafka producer: {error}"))
+        })?;
+        let transaction = match cfg.transactional_id {
+            Some(_) => {
+                Self::transact(&producer, cfg.message_timeout, Producer::init_transactions)
+                    .await
+                    .map_err(|error| {
+                        PublisherError::Generic(format!(
+                            "failed to initialize Kafka transactions: {error}"
+                        ))
+                    })?;
+                Some(Mutex::new(TransactionState::Ready))
+            }
+            None => None,
+        };
+        Ok(KafkaPublisher {
+            producer,
+            partition_key,
+            partition_metadata_field: cfg.partition_metadata_field,
+            header_metadata: cfg.header_metadata,
+            encoder: Encoder::new(cfg.encoding),
+            transaction,
+            transaction_timeout: cfg.message_timeout,
+        })
+    }
+
+    fn client_config(cfg: &KafkaPublisherConfig) -> Result<ClientConfig, PublisherError> {
+        let mut client_config = ClientConfig::new();
+        client_config
             .set("bootstrap.servers", cfg.brokers.join(","))
-            .set("client.id", cfg.client_id)
+            .set("client.id", &cfg.client_id)
             .set("compression.type", cfg.compression.to_string())
             .set("acks", cfg.required_acks.to_string())
             .set(
@@ -85,16 +342,183 @@ impl KafkaPublisher {
             .set(
                 "request.timeout.ms",
                 cfg.ack_timeout.as_millis().to_string(),
-            )
-            .create()
-            .map_err(|error| {
-                PublisherError::Generic(format!("failed to create Kafka producer: {error}"))
-            })?;
-        Ok(KafkaPublisher {
-            producer,
-            metadata_field_as_key: cfg.metadata_field_as_key,
+            );
+
+        if cfg.enable_idempotence || cfg.transactional_id.is_some() {
+            if cfg.required_acks != KRequiredAcks::All {
+                return Err(PublisherError::Generic(
+                    "Kafka idempotence and transactions require required_acks = \"all\""
+                        .to_string(),
+                ));
+            }
+            client_config.set("enable.idempotence", "true");
+        }
+        if let Some(transactional_id) = &cfg.transactional_id {
+            client_config.set("transactional.id", transactional_id);
+        }
+        if let Some(partitioner) = &cfg.partitioner {
+            client_config.set("partitioner", partitioner.to_string());
+        }
+
+        let security = match &cfg.security {
+            Some(security) => security.properties()?,
+            None => Vec::new(),
+        };
+        for (name, value) in &security {
+            client_config.set(*name, value);
+        }
+
+        for (name, value) in &cfg.extra_properties {
+            let typed = TYPED_PROPERTIES.contains(&name.as_str())
+                || security.iter().any(|(property, _)| property == name);
+            if typed {
+                return Err(PublisherError::Generic(format!(
+                    "Kafka extra property '{name}' conflicts with a typed setting"
+                )));
+            }
+            client_config.set(name, value);
+        }
+        Ok(client_config)
+    }
+
+    fn record(&self, payload: &Event, context: &PublishContext) -> Result<Record, PublisherError> {
+        let message = self.encoder.encode(payload, context)?;
+        let headers = self.headers(payload).iter().chain(&message.headers).fold(
+            OwnedHeaders::new(),
+            |headers, (name, value)| {
+                headers.insert(Header {
+                    key: name,
+                    value: Some(value),
+                })
+            },
+        );
+        Ok(Record {
+            key: context
+                .partition_key
+                .as_ref()
+                .unwrap_or(&self.partition_key)
+                .key(payload),
+            partition: self.partition(payload, context)?,
+            value: message.payload.into_bytes(),
+            headers,
         })
     }
+
+    /// Event headers, matching the ones PGMQ messages carry, followed by the
+    /// configured metadata keys and the trace context.
+    fn headers(&self, payload: &Event) -> Vec<(String, String)> {
+        let mut headers = vec![
+            ("event_id".to_string(), payload.id.to_string()),
+            ("event_type".to_string(), payload.event_type.clone()),
+        ];
+        if let Some(version) = &payload.event_version {
+            headers.push(("event_version".to_string(), version.clone()));
+        }
+
+        let transport_metadata = self.encoder.transport_metadata(payload);
+        let lookup = |key: &str| {
+            let value = payload.metadata.get(key);
+            if key == TRACEPARENT {
+                // The trace context is not transport metadata, so the
+                // allowlist does not apply to it.
+                return value.or(payload.ingest.traceparent.as_ref()).cloned();
+            }
+            value
+                .or_else(|| transport_metadata.as_ref()?.get(key))
+                .cloned()
+        };
+        for key in &self.header_metadata {
+            if let Some(value) = lookup(key) {
+                headers.push((key.clone(), value));
+            }
+        }
+        if !self.header_metadata.iter().any(|key| key == TRACEPARENT) {
+            if let Some(traceparent) = lookup(TRACEPARENT) {
+                headers.push((TRACEPARENT.to_string(), traceparent));
+            }
+        }
+        headers
+    }
+
+    /// Partition named by the rule's or the publisher's partition metadata
+    /// field; the partitioner picks one when the field is unset or missing.
+    fn partition(
+        &self,
+        payload: &Event,
+        context: &PublishContext,
+    ) -> Result<Option<i32>, PublisherError> {
+        let Some(field) = context
+            .partition_metadata_field
+            .as_ref()
+            .or(self.partition_metadata_field.as_ref())
+        else {
+            return Ok(None);
+        };
+        payload
+            .metadata
+            .get(field)
+            .map(|value| {
+                value.trim().parse::<i32>().ok().filter(|partition| *partition >= 0).ok_or_else(|| {
+                    PublisherError::Generic(format!(
+                        "event '{}' has an invalid Kafka partition '{value}' in metadata field '{field}'",
+                        payload.id
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    async fn send(&self, topic: &str, record: Record) -> Result<(), PublisherError> {
+        let mut future_record = FutureRecord::to(topic)
+            .payload(&record.value)
+            .headers(record.headers);
+        if let Some(key) = &record.key {
+            future_record = future_record.key(key);
+        }
+        if let Some(partition) = record.partition {
+            future_record = future_record.partition(partition);
+        }
+        self.producer
+            .send(future_record, Duration::ZERO)
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| PublisherError::Generic(e.to_string()))
+    }
+
+    async fn send_all(
+        &self,
+        batch: &[PublishRequest<Event>],
+        records: Vec<Result<Record, PublisherError>>,
+    ) -> Vec<Result<(), PublisherError>> {
+        // Enqueue every record before awaiting so librdkafka can batch them
+        // instead of waiting for one delivery report per event.
+        let deliveries = batch
+            .iter()
+            .zip(records)
+            .map(|(request, record)| async move { self.send(&request.topic, record?).await });
+        join_all(deliveries).await
+    }
+
+    /// Runs a blocking transaction call off the async runtime.
+    async fn transact(
+        producer: &FutureProducer,
+        timeout: Duration,
+        operation: fn(&FutureProducer, Duration) -> KafkaResult<()>,
+    ) -> Result<(), PublisherError> {
+        let producer = producer.clone();
+        task::spawn_blocking(move || operation(&producer, timeout))
+            .await
+            .map_err(|e| PublisherError::Generic(e.to_string()))?
+            .map_err(|e| PublisherError::Generic(e.to_string()))
+    }
+
+    /// Stops further transactions once librdkafka reports a fatal error.
+    fn check_fatal(&self, state: &mut TransactionState) {
+        if let Some((code, reason)) = self.producer.client().fatal_error() {
+            error!("Kafka producer failed fatally ({code}): {reason}");
+            *state = TransactionState::Failed;
+        }
+    }
 }
 
 #[async_trait]
@@ -103,40 +527,423 @@ impl Publisher<Event> for KafkaPublisher {
         &self,
         topic: &str,
         payload: Event,
-        _context: PublishContext,
+        context: PublishContext,
     ) -> Result<(), PublisherError> {
-        let default_key = &payload.id.to_string();
-        let key = self
-            .metadata_field_as_key
-            .as_ref()
-            .and_then(|k| payload.metadata.get(k))
-            .unwrap_or(default_key);
-        let value =
-            serde_json::to_string(&payload).map_err(|e| PublisherError::Generic(e.to_string()))?;
-        let result = self
-            .producer
-            .send(
-                FutureRecord::to(topic).key(key).payload(&value),
-                Duration::ZERO,
+        if self.transaction.is_some() {
+            let request = PublishRequest {
+                topic: topic.to_string(),
+                payload,
+                context,
+            };
+            return self.publish_batch(vec![request]).await.remove(0);
+        }
+        let record = self.record(&payload, &context)?;
+        self.send(topic, record).await
+    }
+
+    fn topic_naming(&self) -> TopicNaming {
+        TopicNaming::Kafka
+    }
+
+    async fn publish_batch(
+        &self,
+        batch: Vec<PublishRequest<Event>>,
+    ) -> Vec<Result<(), PublisherError>> {
+        let records = batch
+            .iter()
+            .map(|request| self.record(&request.payload, &request.context))
+            .collect::<Vec<Result<Record, PublisherError>>>();
+
+        let Some(transaction) = &self.transaction else {
+            return self.send_all(&batch, records).await;
+        };
+
+        let mut state = transaction.lock().await;
+        if *state == TransactionState::Failed {
+            let error = PublisherError::Generic(
+                "Kafka producer is unusable after a fatal transaction error".to_string(),
+            );
+            return batch.iter().map(|_| Err(error.clone())).collect();
+        }
+        if let Err(error) = self.producer.begin_transaction() {
+            self.check_fatal(&mut state);
+            let error =
+                PublisherError::Generic(format!("failed to begin Kafka transaction: {error}"));
+            return batch.iter().map(|_| Err(error.clone())).collect();
+        }
+        let outcome = match records.iter().find_map(|record| record.as_ref().err()) {
+            Some(error) => Err(error.clone()),
+            None => {
+                let results = self.send_all(&batch, records).await;
+                match results.into_iter().find_map(Result::err) {
+                    Some(error) => Err(error),
+                    None => {
+                        Self::transact(
+                            &self.producer,
+                            self.transaction_timeout,
+                            Producer::commit_transaction,
+                        )
+                        .await
+                    }
+                }
+            }
+        };
+        if let Err(error) = outcome {
+            let abort = Self::transact(
+                &self.producer,
+                self.transaction_timeout,
+                Producer::abort_transaction,
             )
             .await;
-        result
-            .map(|_| ())
-            .map_err(|(e, _)| PublisherError::Generic(e.to_string()))
+            match abort {
+                Ok(()) => self.check_fatal(&mut state),
+                Err(abort) => {
+                    error!("Failed to abort Kafka transaction, stopping transactions: {abort}");
+                    *state = TransactionState::Failed;
+                }
+            }
+            let error = PublisherError::Generic(format!("Kafka transaction aborted: {error}"));
+            return batch.iter().map(|_| Err(error.clone())).collect();
+        }
+        batch.iter().map(|_| Ok(())).collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{KCompression, KRequiredAcks};
+    use super::*;
+    use crate::model::event::{Data, IngestContext};
+    use rdkafka::{
+        consumer::{Consumer, StreamConsumer},
+        message::{Headers, Message},
+        mocking::MockCluster,
+        producer::DefaultProducerContext,
+        types::{RDKafkaApiKey, RDKafkaRespErr},
+    };
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn config(brokers: String) -> KafkaPublisherConfig {
+        KafkaPublisherConfig {
+            brokers: vec![brokers],
+            compression: KCompression::None,
+            client_id: "event-gateway-test".to_string(),
+            required_acks: KRequiredAcks::All,
+            conn_idle_timeout: Duration::from_secs(60),
+            message_timeout: Duration::from_secs(5),
+            ack_timeout: Duration::from_secs(5),
+            metadata_field_as_key: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            partitioner: None,
+            enable_idempotence: false,
+            transactional_id: None,
+            security: None,
+            extra_properties: HashMap::new(),
+            header_metadata: vec!["tenant_id".to_string(), "jwt_sub".to_string()],
+            encoding: EncodingConfig::default(),
+        }
+    }
 
     #[test]
     fn maps_kafka_configuration_values() {
         assert_eq!(KCompression::None.to_string(), "none");
         assert_eq!(KCompression::Gzip.to_string(), "gzip");
         assert_eq!(KCompression::Snappy.to_string(), "snappy");
+        assert_eq!(KCompression::Lz4.to_string(), "lz4");
+        assert_eq!(KCompression::Zstd.to_string(), "zstd");
         assert_eq!(KRequiredAcks::None.to_string(), "0");
         assert_eq!(KRequiredAcks::One.to_string(), "1");
         assert_eq!(KRequiredAcks::All.to_string(), "all");
     }
+
+    fn event() -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: Some("1".to_string()),
+            metadata: HashMap::from([("tenant_id".to_string(), "acme".to_string())]),
+            transport_metadata: Some(HashMap::from([(
+                "jwt_sub".to_string(),
+                "client".to_string(),
+            )])),
+            data_type: None,
+            data: Data::String("created".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: IngestContext {
+                traceparent: Some(
+                    "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string(),
+                ),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn consumer(
+        cluster: &MockCluster<'_, DefaultProducerContext>,
+        topics: &[&str],
+    ) -> StreamConsumer {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", cluster.bootstrap_servers())
+            .set("group.id", "event-gateway-test")
+            .set("auto.offset.reset", "earliest")
+            .set("isolation.level", "read_committed")
+            .create()
+            .unwrap();
+        consumer.subscribe(topics).unwrap();
+        consumer
+    }
+
+    fn security(protocol: KSecurityProtocol) -> KafkaSecurityConfig {
+        KafkaSecurityConfig {
+            protocol,
+            sasl_mechanism: Some(KSaslMechanism::ScramSha512),
+            sasl_username: Some("gateway".to_string()),
+            sasl_password: Some("secret".to_string()),
+            ssl_ca_location: Some("/etc/kafka/ca.pem".to_string()),
+            ssl_certificate_location: None,
+            ssl_key_location: None,
+            ssl_key_password: None,
+        }
+    }
+
+    fn error(cfg: KafkaPublisherConfig) -> String {
+        match KafkaPublisher::client_config(&cfg) {
+            Ok(_) => panic!("expected invalid Kafka settings"),
+            Err(PublisherError::Generic(message)) => message,
+        }
+    }
+
+    #[tokio::test]
+    async fn validates_security_and_extra_properties() {
+        let mut cfg = config("127.0.0.1:9092".to_string());
+        cfg.security = Some(Box::new(security(KSecurityProtocol::SaslSsl)));
+        cfg.extra_properties = HashMap::from([("linger.ms".to_string(), "10".to_string())]);
+        let client_config = KafkaPublisher::client_config(&cfg).unwrap();
+        assert_eq!(client_config.get("security.protocol"), Some("sasl_ssl"));
+        assert_eq!(client_config.get("sasl.mechanism"), Some("SCRAM-SHA-512"));
+        assert_eq!(
+            client_config.get("ssl.ca.location"),
+            Some("/etc/kafka/ca.pem")
+        );
+        assert_eq!(client_config.get("linger.ms"), Some("10"));
+
+        cfg.security = Some(Box::new(security(KSecurityProtocol::SaslPlaintext)));
+        assert!(error(cfg.clone()).contains("does not use SSL settings"));
+
+        let mut sasl = security(KSecurityProtocol::SaslSsl);
+        sasl.sasl_password = None;
+        cfg.security = Some(Box::new(sasl));
+        assert!(error(cfg.clone()).contains("requires sasl_password"));
+
+        cfg.security = Some(Box::new(security(KSecurityProtocol::SaslSsl)));
+        cfg.extra_properties = HashMap::from([("sasl.mechanism".to_string(), "PLAIN".to_string())]);
+        assert!(error(cfg.clone()).contains("conflicts with a typed setting"));
+
+        cfg.extra_properties = HashMap::from([("no.such.property".to_string(), "1".to_string())]);
+        match KafkaPublisher::new(cfg).await {
+            Ok(_) => panic!("expected an unknown Kafka property to be rejected"),
+            Err(PublisherError::Generic(message)) => assert!(
+                message.contains("No such configuration property: \"no.such.property\""),
+                "{message}"
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn creates_producer_with_scram_over_ssl_and_zstd() {
+        let mut cfg = config("127.0.0.1:9092".to_string());
+        let mut security = security(KSecurityProtocol::SaslSsl);
+        security.ssl_ca_location = None;
+        cfg.security = Some(Box::new(security));
+        cfg.compression = KCompression::Zstd;
+        if let Err(error) = KafkaPublisher::new(cfg).await {
+            panic!("{error}");
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_event_headers() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("orders", 1, 1).unwrap();
+        let publisher = KafkaPublisher::new(config(cluster.bootstrap_servers()))
+            .await
+            .unwrap();
+        let event = event();
+        publisher
+            .publish_one("orders", event.clone(), PublishContext::default())
+            .await
+            .unwrap();
+
+        let consumer = consumer(&cluster, &["orders"]);
+        let message = tokio::time::timeout(Duration::from_secs(10), consumer.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let headers: Vec<(String, String)> = message
+            .headers()
+            .unwrap()
+            .iter()
+            .map(|header| {
+                (
+                    header.key.to_string(),
+                    String::from_utf8(header.value.unwrap().to_vec()).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                ("event_id".to_string(), event.id.to_string()),
+                ("event_type".to_string(), "order.created".to_string()),
+                ("event_version".to_string(), "1".to_string()),
+                ("tenant_id".to_string(), "acme".to_string()),
+                (
+                    TRACEPARENT.to_string(),
+                    "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_batches_in_transactions() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("orders", 1, 1).unwrap();
+        cluster.create_topic("audit", 1, 1).unwrap();
+        let mut cfg = config(cluster.bootstrap_servers());
+        cfg.transactional_id = Some("event-gateway-test".to_string());
+        let publisher = KafkaPublisher::new(cfg).await.unwrap();
+        let request = |topic: &str| PublishRequest {
+            topic: topic.to_string(),
+            payload: event(),
+            context: PublishContext::default(),
+        };
+
+        let results = publisher
+            .publish_batch(vec![request("orders"), request("audit")])
+            .await;
+        assert!(results.iter().all(Result::is_ok));
+        let consumer = consumer(&cluster, &["orders", "audit"]);
+        let mut topics = Vec::new();
+        for _ in 0..2 {
+            let message = tokio::time::timeout(Duration::from_secs(10), consumer.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            topics.push(message.topic().to_string());
+        }
+        topics.sort();
+        assert_eq!(topics, vec!["audit", "orders"]);
+
+        cluster
+            .topic_error(
+                "audit",
+                RDKafkaRespErr::RD_KAFKA_RESP_ERR_TOPIC_AUTHORIZATION_FAILED,
+            )
+            .unwrap();
+        let results = publisher
+            .publish_batch(vec![request("orders"), request("audit")])
+            .await;
+        assert!(results.iter().all(|result| result
+            .as_ref()
+            .is_err_and(|error| error.to_string().starts_with("Kafka transaction aborted"))));
+    }
+
+    #[tokio::test]
+    async fn stops_transactions_after_fatal_errors() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("orders", 1, 1).unwrap();
+        let mut cfg = config(cluster.bootstrap_servers());
+        cfg.transactional_id = Some("event-gateway-test".to_string());
+        let publisher = KafkaPublisher::new(cfg).await.unwrap();
+        let request = || PublishRequest {
+            topic: "orders".to_string(),
+            payload: event(),
+            context: PublishContext::default(),
+        };
+
+        cluster.request_errors(
+            RDKafkaApiKey::EndTxn,
+            &[RDKafkaRespErr::RD_KAFKA_RESP_ERR_PRODUCER_FENCED],
+        );
+        let results = publisher.publish_batch(vec![request()]).await;
+        assert!(results[0].is_err());
+        cluster.clear_request_errors(RDKafkaApiKey::EndTxn);
+
+        let results = publisher.publish_batch(vec![request()]).await;
+        assert!(results[0]
+            .as_ref()
+            .is_err_and(|error| error.to_string().contains("unusable")));
+    }
+
+    #[tokio::test]
+    async fn selects_record_keys_and_partitions() {
+        let mut cfg = config("127.0.0.1:9092".to_string());
+        cfg.metadata_field_as_key = Some("tenant_id".to_string());
+        cfg.partition_metadata_field = Some("partition".to_string());
+        let publisher = KafkaPublisher::new(cfg.clone()).await.unwrap();
+        let mut event = event();
+        event.data = Data::Json(HashMap::from([(
+            "order".to_string(),
+            serde_json::json!({"id": 42}),
+        )]));
+        let record =
+            |event: &Event, context: PublishContext| publisher.record(event, &context).unwrap();
+
+        let default = record(&event, PublishContext::default());
+        assert_eq!(default.key.as_deref(), Some("acme"));
+        assert_eq!(default.partition, None);
+
+        let rule_key = |key: PartitionKey| {
+            record(
+                &event,
+                PublishContext {
+                    partition_key: Some(key),
+                    ..PublishContext::default()
+                },
+            )
+            .key
+        };
+        assert_eq!(
+            rule_key(PartitionKey::Payload {
+                pointer: "/order/id".to_string()
+            }),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            rule_key(PartitionKey::Composite {
+                fields: vec!["tenant_id".to_string(), "region".to_string()],
+                separator: ":".to_string(),
+            }),
+            Some(event.id.to_string())
+        );
+        assert_eq!(rule_key(PartitionKey::Null), None);
+
+        event
+            .metadata
+            .insert("partition".to_string(), "3".to_string());
+        assert_eq!(record(&event, PublishContext::default()).partition, Some(3));
+        event
+            .metadata
+            .insert("partition".to_string(), "three".to_string());
+        assert!(publisher
+            .record(&event, &PublishContext::default())
+            .is_err());
+
+        cfg.partition_key = Some(PartitionKey::Null);
+        assert!(KafkaPublisher::new(cfg).await.is_err());
+    }
+
+    #[test]
+    fn idempotence_requires_all_acks() {
+        let mut cfg = config("127.0.0.1:9092".to_string());
+        cfg.enable_idempotence = true;
+        let client_config = KafkaPublisher::client_config(&cfg).unwrap();
+        assert_eq!(client_config.get("enable.idempotence"), Some("true"));
+
+        cfg.required_acks = KRequiredAcks::One;
+        assert!(error(cfg).contains("require required_acks"));
+    }
 }
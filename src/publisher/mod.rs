@@ -1,4 +1,8 @@
+pub mod amqp_publisher;
+pub mod format;
 pub mod kafka_publisher;
 pub mod mqtt_publisher;
+pub mod nats_publisher;
 pub mod pgmq_publisher;
 pub mod publisher;
+pub mod redis_streams_publisher;
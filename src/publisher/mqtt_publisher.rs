@@ -1,9 +1,37 @@
 use crate::model::event::Event;
+use crate::model::publishing::{MqttTopicMapping, OutputFormat};
+use crate::model::topic::TopicNaming;
+use crate::publisher::format::{EncodedMessage, Encoder, EncodingConfig};
 use crate::publisher::publisher::{PublishContext, Publisher, PublisherError};
 use async_trait::async_trait;
-use rumqttc::{AsyncClient, MqttOptions, QoS};
+use duration_str::deserialize_duration;
+use log::{info, warn};
+use rumqttc::tokio_native_tls::native_tls::{Certificate, Identity, TlsConnector};
+use rumqttc::v5::mqttbytes::v5::{
+    LastWill as LastWillV5, Packet as PacketV5, PubAckReason, PubRecReason, PublishProperties,
+};
+use rumqttc::v5::mqttbytes::QoS as QoSV5;
+use rumqttc::v5::{
+    AsyncClient as AsyncClientV5, ConnectionError as ConnectionErrorV5, Event as MqttEventV5,
+    EventLoop as EventLoopV5, MqttOptions as MqttOptionsV5,
+};
+use rumqttc::{
+    AsyncClient, ConnectionError, Event as MqttEvent, EventLoop, LastWill, MqttOptions, Outgoing,
+    Packet, QoS, TlsConfiguration, Transport,
+};
 use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
 use std::time::Duration;
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::time;
+
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(500);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -24,6 +52,56 @@ impl From<QosLevel> for QoS {
     }
 }
 
+impl From<QosLevel> for QoSV5 {
+    fn from(qos: QosLevel) -> Self {
+        match qos {
+            QosLevel::AtMostOnce => QoSV5::AtMostOnce,
+            QosLevel::AtLeastOnce => QoSV5::AtLeastOnce,
+            QosLevel::ExactlyOnce => QoSV5::ExactlyOnce,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum MqttVersion {
+    #[default]
+    #[serde(rename = "3.1.1")]
+    V311,
+    #[serde(rename = "5")]
+    V5,
+}
+
+impl fmt::Display for MqttVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MqttVersion::V311 => write!(f, "3.1.1"),
+            MqttVersion::V5 => write!(f, "5"),
+        }
+    }
+}
+
+/// PEM files for TLS connections; the system roots are trusted without
+/// `ca_location`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MqttTlsConfig {
+    pub ca_location: Option<String>,
+    pub certificate_location: Option<String>,
+    /// PKCS #8 private key of the client certificate.
+    pub key_location: Option<String>,
+}
+
+/// Message the broker publishes when the gateway disconnects ungracefully.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MqttLastWillConfig {
+    pub topic: String,
+    pub payload: String,
+    pub qos: QosLevel,
+    #[serde(default)]
+    pub retain: bool,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct MqttPublisherConfig {
     pub host: String,
@@ -33,55 +111,724 @@ pub struct MqttPublisherConfig {
     pub clean_session: bool,
     pub qos: QosLevel,
     pub retain: bool,
+    /// How long a publish waits for the broker to acknowledge it at `qos`.
+    #[serde(
+        default = "default_ack_timeout",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub ack_timeout: Duration,
+    #[serde(default)]
+    pub protocol_version: MqttVersion,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: Option<MqttTlsConfig>,
+    pub last_will: Option<MqttLastWillConfig>,
+    /// MQTT 5 message expiry interval.
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub message_expiry: Option<Duration>,
+    #[serde(default)]
+    pub topic_mapping: MqttTopicMapping,
+    #[serde(flatten)]
+    pub encoding: EncodingConfig,
+}
+
+fn default_ack_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+/// Broker verdict on a publish.
+type Ack = oneshot::Sender<Result<(), String>>;
+
+/// Publishes waiting for the broker. The event loop sends publishes in the
+/// order they were handed to the client, which is how a packet id is matched
+/// to its waiting publish.
+#[derive(Default)]
+struct Acks {
+    /// Publishes handed to the client and not yet sent.
+    queued: VecDeque<Ack>,
+    /// Sent QoS 1 and 2 publishes, by packet id.
+    in_flight: HashMap<u16, Ack>,
+}
+
+impl Acks {
+    fn sent(&mut self, pkid: u16) {
+        // Publishes resent after a reconnect keep their packet id.
+        if self.in_flight.contains_key(&pkid) {
+            return;
+        }
+        let Some(ack) = self.queued.pop_front() else {
+            return;
+        };
+        if pkid == 0 {
+            let _ = ack.send(Ok(()));
+        } else {
+            self.in_flight.insert(pkid, ack);
+        }
+    }
+
+    fn acknowledged(&mut self, pkid: u16, result: Result<(), String>) {
+        if let Some(ack) = self.in_flight.remove(&pkid) {
+            let _ = ack.send(result);
+        }
+    }
+}
+
+enum Client {
+    V311(AsyncClient),
+    V5(AsyncClientV5),
 }
 
 pub struct MqttPublisher {
-    client: AsyncClient,
-    qos: QoS,
+    client: Client,
+    qos: QosLevel,
     retain: bool,
+    message_expiry: Option<Duration>,
+    topic_mapping: MqttTopicMapping,
+    encoder: Encoder,
+    ack_timeout: Duration,
+    acks: Arc<Mutex<Acks>>,
+    /// Keeps queued acknowledgments in the order requests reach the client.
+    enqueue: AsyncMutex<()>,
+    connected: Arc<AtomicBool>,
 }
 
-impl MqttPublisher {
-    pub fn new(config: MqttPublisherConfig) -> Self {
-        let mut mqttoptions = MqttOptions::new(config.client_id, config.host, config.port);
-        mqttoptions.set_keep_alive(Duration::from_secs(config.keep_alive));
-        mqttoptions.set_clean_session(config.clean_session);
+/// MQTT 3.1.1 messages have no headers to carry binary-mode attributes; MQTT 5
+/// carries them as user properties.
+fn supports_format(version: MqttVersion, format: OutputFormat) -> bool {
+    version == MqttVersion::V5 || format != OutputFormat::CloudeventsBinary
+}
 
-        let (client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
+fn invalid(message: impl Into<String>) -> PublisherError {
+    PublisherError::Generic(format!("invalid MQTT settings: {}", message.into()))
+}
 
-        // Spawn a task to handle connection events
-        tokio::spawn(async move {
-            loop {
-                if let Err(e) = eventloop.poll().await {
-                    eprintln!("MQTT Event Loop Error: {e:?}");
+fn read_pem(path: &str) -> Result<Vec<u8>, PublisherError> {
+    std::fs::read(path).map_err(|e| invalid(format!("cannot read '{path}': {e}")))
+}
+
+impl MqttTlsConfig {
+    fn transport(&self) -> Result<Transport, PublisherError> {
+        let mut connector = TlsConnector::builder();
+        if let Some(ca) = &self.ca_location {
+            let certificate = Certificate::from_pem(&read_pem(ca)?)
+                .map_err(|e| invalid(format!("invalid CA certificate '{ca}': {e}")))?;
+            connector.add_root_certificate(certificate);
+        }
+        match (&self.certificate_location, &self.key_location) {
+            (Some(certificate), Some(key)) => {
+                let identity = Identity::from_pkcs8(&read_pem(certificate)?, &read_pem(key)?)
+                    .map_err(|e| invalid(format!("invalid client certificate or key: {e}")))?;
+                connector.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(invalid(
+                    "tls certificate_location and key_location must be set together",
+                ))
+            }
+        }
+        let connector = connector
+            .build()
+            .map_err(|e| invalid(format!("cannot create TLS connector: {e}")))?;
+        Ok(Transport::tls_with_config(
+            TlsConfiguration::NativeConnector(connector),
+        ))
+    }
+}
+
+impl MqttPublisher {
+    pub fn new(config: MqttPublisherConfig) -> Result<Self, PublisherError> {
+        let version = config.protocol_version;
+        if !supports_format(version, config.encoding.format) {
+            return Err(PublisherError::Generic(format!(
+                "MQTT {version} does not support the {} format",
+                OutputFormat::CloudeventsBinary
+            )));
+        }
+        if config.message_expiry.is_some() && version != MqttVersion::V5 {
+            return Err(invalid("message_expiry requires protocol_version \"5\""));
+        }
+        let credentials = match (config.username, config.password) {
+            (Some(username), password) => Some((username, password.unwrap_or_default())),
+            (None, None) => None,
+            (None, Some(_)) => return Err(invalid("password requires username")),
+        };
+        let transport = config
+            .tls
+            .as_ref()
+            .map(MqttTlsConfig::transport)
+            .transpose()?;
+        let keep_alive = Duration::from_secs(config.keep_alive);
+
+        let acks = Arc::new(Mutex::new(Acks::default()));
+        let connected = Arc::new(AtomicBool::new(false));
+        let client = match version {
+            MqttVersion::V311 => {
+                let mut options = MqttOptions::new(config.client_id, config.host, config.port);
+                options.set_keep_alive(keep_alive);
+                options.set_clean_session(config.clean_session);
+                if let Some((username, password)) = credentials {
+                    options.set_credentials(username, password);
+                }
+                if let Some(transport) = transport {
+                    options.set_transport(transport);
+                }
+                if let Some(will) = config.last_will {
+                    options.set_last_will(LastWill::new(
+                        will.topic,
+                        will.payload,
+                        will.qos.into(),
+                        will.retain,
+                    ));
                 }
+                let (client, eventloop) = AsyncClient::new(options, 10);
+                tokio::spawn(run_event_loop(
+                    eventloop,
+                    Arc::clone(&acks),
+                    Arc::clone(&connected),
+                ));
+                Client::V311(client)
             }
-        });
+            MqttVersion::V5 => {
+                let mut options = MqttOptionsV5::new(config.client_id, config.host, config.port);
+                options.set_keep_alive(keep_alive);
+                options.set_clean_start(config.clean_session);
+                if let Some((username, password)) = credentials {
+                    options.set_credentials(username, password);
+                }
+                if let Some(transport) = transport {
+                    options.set_transport(transport);
+                }
+                if let Some(will) = config.last_will {
+                    options.set_last_will(LastWillV5::new(
+                        will.topic,
+                        will.payload,
+                        will.qos.into(),
+                        will.retain,
+                        None,
+                    ));
+                }
+                let (client, eventloop) = AsyncClientV5::new(options, 10);
+                tokio::spawn(run_event_loop_v5(
+                    eventloop,
+                    Arc::clone(&acks),
+                    Arc::clone(&connected),
+                ));
+                Client::V5(client)
+            }
+        };
 
-        MqttPublisher {
+        Ok(MqttPublisher {
             client,
-            qos: config.qos.into(),
+            qos: config.qos,
             retain: config.retain,
+            message_expiry: config.message_expiry,
+            topic_mapping: config.topic_mapping,
+            encoder: Encoder::new(config.encoding),
+            ack_timeout: config.ack_timeout,
+            acks,
+            enqueue: AsyncMutex::new(()),
+            connected,
+        })
+    }
+
+    fn version(&self) -> MqttVersion {
+        match self.client {
+            Client::V311(_) => MqttVersion::V311,
+            Client::V5(_) => MqttVersion::V5,
+        }
+    }
+
+    /// MQTT 5 properties of a message: its content type, the event id, type
+    /// and version, metadata and, in binary mode, the CloudEvents attributes
+    /// as user properties.
+    fn properties(
+        &self,
+        event: &Event,
+        format: OutputFormat,
+        message: &EncodedMessage,
+    ) -> PublishProperties {
+        let mut user_properties = vec![
+            ("event_id".to_string(), event.id.to_string()),
+            ("event_type".to_string(), event.event_type.clone()),
+        ];
+        if let Some(version) = &event.event_version {
+            user_properties.push(("event_version".to_string(), version.clone()));
+        }
+        let mut metadata: Vec<_> = event.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            user_properties.push((key.clone(), value.clone()));
+        }
+        for (name, value) in &message.headers {
+            match name.strip_prefix("ce-") {
+                Some(attribute) => user_properties.push((attribute.to_string(), value.clone())),
+                None if name == "content-type" => {}
+                None => user_properties.push((name.clone(), value.clone())),
+            }
+        }
+
+        PublishProperties {
+            content_type: Some(message.content_type(format)),
+            user_properties,
+            message_expiry_interval: self
+                .message_expiry
+                .map(|expiry| u32::try_from(expiry.as_secs()).unwrap_or(u32::MAX)),
+            ..PublishProperties::default()
+        }
+    }
+
+    async fn send(
+        &self,
+        topic: &str,
+        event: &Event,
+        format: OutputFormat,
+        message: EncodedMessage,
+    ) -> Result<(), String> {
+        match &self.client {
+            Client::V311(client) => client
+                .publish(
+                    topic,
+                    self.qos.clone().into(),
+                    self.retain,
+                    message.payload.into_bytes(),
+                )
+                .await
+                .map_err(|e| e.to_string()),
+            Client::V5(client) => {
+                let properties = self.properties(event, format, &message);
+                client
+                    .publish_with_properties(
+                        topic,
+                        self.qos.clone().into(),
+                        self.retain,
+                        message.payload.into_bytes(),
+                        properties,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// Waits before the next connection attempt, doubling the wait up to
+/// [`RECONNECT_BACKOFF_MAX`].
+async fn reconnect(connected: &AtomicBool, backoff: &mut Duration, error: impl fmt::Display) {
+    connected.store(false, Ordering::Relaxed);
+    warn!("MQTT connection failed, reconnecting in {backoff:?}: {error}");
+    time::sleep(*backoff).await;
+    *backoff = (*backoff * 2).min(RECONNECT_BACKOFF_MAX);
+}
+
+fn connection_established(connected: &AtomicBool, backoff: &mut Duration) {
+    info!("Connected to MQTT broker");
+    connected.store(true, Ordering::Relaxed);
+    *backoff = RECONNECT_BACKOFF_MIN;
+}
+
+/// Drives an MQTT 3.1.1 connection until the publisher is dropped,
+/// completing acknowledged publishes and reconnecting with exponential
+/// backoff.
+async fn run_event_loop(
+    mut eventloop: EventLoop,
+    acks: Arc<Mutex<Acks>>,
+    connected: Arc<AtomicBool>,
+) {
+    let mut backoff = RECONNECT_BACKOFF_MIN;
+    loop {
+        match eventloop.poll().await {
+            Ok(MqttEvent::Incoming(Packet::ConnAck(_))) => {
+                connection_established(&connected, &mut backoff)
+            }
+            Ok(MqttEvent::Outgoing(Outgoing::Publish(pkid))) => acks.lock().unwrap().sent(pkid),
+            Ok(MqttEvent::Incoming(Packet::PubAck(ack))) => {
+                acks.lock().unwrap().acknowledged(ack.pkid, Ok(()))
+            }
+            Ok(MqttEvent::Incoming(Packet::PubComp(ack))) => {
+                acks.lock().unwrap().acknowledged(ack.pkid, Ok(()))
+            }
+            Ok(_) => {}
+            Err(ConnectionError::RequestsDone) => break,
+            Err(e) => reconnect(&connected, &mut backoff, e).await,
+        }
+    }
+}
+
+/// MQTT 5 counterpart of [`run_event_loop`], which also fails publishes the
+/// broker rejects with a reason code.
+async fn run_event_loop_v5(
+    mut eventloop: EventLoopV5,
+    acks: Arc<Mutex<Acks>>,
+    connected: Arc<AtomicBool>,
+) {
+    let mut backoff = RECONNECT_BACKOFF_MIN;
+    loop {
+        match eventloop.poll().await {
+            Ok(MqttEventV5::Incoming(PacketV5::ConnAck(_))) => {
+                connection_established(&connected, &mut backoff)
+            }
+            Ok(MqttEventV5::Outgoing(Outgoing::Publish(pkid))) => acks.lock().unwrap().sent(pkid),
+            Ok(MqttEventV5::Incoming(PacketV5::PubAck(ack))) => {
+                let result = match ack.reason {
+                    PubAckReason::Success | PubAckReason::NoMatchingSubscribers => Ok(()),
+                    reason => Err(format!("MQTT broker rejected the publish: {reason:?}")),
+                };
+                acks.lock().unwrap().acknowledged(ack.pkid, result)
+            }
+            Ok(MqttEventV5::Incoming(PacketV5::PubRec(rec))) => match rec.reason {
+                PubRecReason::Success | PubRecReason::NoMatchingSubscribers => {}
+                reason => acks.lock().unwrap().acknowledged(
+                    rec.pkid,
+                    Err(format!("MQTT broker rejected the publish: {reason:?}")),
+                ),
+            },
+            Ok(MqttEventV5::Incoming(PacketV5::PubComp(ack))) => {
+                acks.lock().unwrap().acknowledged(ack.pkid, Ok(()))
+            }
+            Ok(_) => {}
+            Err(ConnectionErrorV5::RequestsDone) => break,
+            Err(e) => reconnect(&connected, &mut backoff, e).await,
         }
     }
 }
 
 #[async_trait]
 impl Publisher<Event> for MqttPublisher {
+    fn supports_format(&self, format: OutputFormat) -> bool {
+        supports_format(self.version(), format)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
     async fn publish_one(
         &self,
         topic: &str,
         payload: Event,
-        _context: PublishContext,
+        context: PublishContext,
     ) -> Result<(), PublisherError> {
-        let payload_json =
-            serde_json::to_string(&payload).map_err(|e| PublisherError::Generic(e.to_string()))?;
+        let format = self.encoder.format(&context);
+        if !supports_format(self.version(), format) {
+            return Err(PublisherError::Generic(format!(
+                "MQTT {} does not support the {format} format",
+                self.version()
+            )));
+        }
+        let topic = context
+            .mqtt_topic_mapping
+            .as_ref()
+            .unwrap_or(&self.topic_mapping)
+            .apply(topic, &payload)
+            .map_err(|e| {
+                PublisherError::Generic(format!("invalid MQTT topic for '{topic}': {e}"))
+            })?;
+        let topic = topic.as_str();
+        let message = self.encoder.encode(&payload, &context)?;
+
+        let (ack, acknowledged) = oneshot::channel();
+        {
+            let _enqueue = self.enqueue.lock().await;
+            self.acks.lock().unwrap().queued.push_back(ack);
+            if let Err(e) = self.send(topic, &payload, format, message).await {
+                self.acks.lock().unwrap().queued.pop_back();
+                return Err(PublisherError::Generic(e));
+            }
+        }
+
+        match time::timeout(self.ack_timeout, acknowledged).await {
+            Ok(Ok(result)) => result.map_err(PublisherError::Generic),
+            Ok(Err(_)) => Err(PublisherError::Generic(
+                "MQTT event loop stopped".to_string(),
+            )),
+            Err(_) => Err(PublisherError::Generic(format!(
+                "MQTT broker did not acknowledge the publish to '{topic}' within {:?}",
+                self.ack_timeout
+            ))),
+        }
+    }
+
+    fn topic_naming(&self) -> TopicNaming {
+        TopicNaming::Mqtt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::Data;
+    use crate::model::topic::TopicTemplate;
+    use bytes::BytesMut;
+    use rumqttc::mqttbytes::Error as MqttError;
+    use rumqttc::{ConnAck, ConnectReturnCode, PubAck, PubComp, PubRec, Publish};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
 
-        self.client
-            .publish(topic, self.qos, self.retain, payload_json)
+    /// MQTT 3.1.1 broker for a single client connection that acknowledges
+    /// publishes when `acknowledge` is set, and reports what it receives.
+    async fn broker(acknowledge: bool) -> (u16, mpsc::UnboundedReceiver<Publish>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (published, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = BytesMut::new();
+            loop {
+                let packet = match Packet::read(&mut buffer, 1024 * 1024) {
+                    Ok(packet) => packet,
+                    Err(MqttError::InsufficientBytes(_)) => {
+                        if socket.read_buf(&mut buffer).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        continue;
+                    }
+                    Err(e) => panic!("invalid MQTT packet: {e:?}"),
+                };
+                let reply = match packet {
+                    Packet::Connect(_) => Some(Packet::ConnAck(ConnAck::new(
+                        ConnectReturnCode::Success,
+                        false,
+                    ))),
+                    Packet::Publish(publish) => {
+                        let reply = match publish.qos {
+                            QoS::AtMostOnce => None,
+                            QoS::AtLeastOnce => Some(Packet::PubAck(PubAck::new(publish.pkid))),
+                            QoS::ExactlyOnce => Some(Packet::PubRec(PubRec::new(publish.pkid))),
+                        };
+                        let _ = published.send(publish);
+                        reply.filter(|_| acknowledge)
+                    }
+                    Packet::PubRel(release) => Some(Packet::PubComp(PubComp::new(release.pkid))),
+                    Packet::PingReq => Some(Packet::PingResp),
+                    _ => None,
+                };
+                if let Some(reply) = reply {
+                    let mut out = BytesMut::new();
+                    reply.write(&mut out, 1024 * 1024).unwrap();
+                    socket.write_all(&out).await.unwrap();
+                }
+            }
+        });
+        (port, received)
+    }
+
+    fn config(port: u16, qos: QosLevel, ack_timeout: Duration) -> MqttPublisherConfig {
+        MqttPublisherConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            client_id: "event-gateway-test".to_string(),
+            keep_alive: 30,
+            clean_session: true,
+            qos,
+            retain: false,
+            ack_timeout,
+            protocol_version: MqttVersion::V311,
+            username: None,
+            password: None,
+            tls: None,
+            last_will: None,
+            message_expiry: None,
+            topic_mapping: MqttTopicMapping::default(),
+            encoding: EncodingConfig::default(),
+        }
+    }
+
+    fn event() -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            metadata: HashMap::new(),
+            transport_metadata: None,
+            data_type: None,
+            data: Data::String("created".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn completes_after_broker_acknowledgment() {
+        let (port, mut received) = broker(true).await;
+        let publisher =
+            MqttPublisher::new(config(port, QosLevel::ExactlyOnce, Duration::from_secs(5)))
+                .unwrap();
+        let events = [event(), event()];
+        let (first, second) = tokio::join!(
+            publisher.publish_one("orders", events[0].clone(), PublishContext::default()),
+            publisher.publish_one("audit", events[1].clone(), PublishContext::default()),
+        );
+        first.unwrap();
+        second.unwrap();
+        assert!(publisher.is_connected());
+
+        let mut topics = vec![
+            received.recv().await.unwrap().topic,
+            received.recv().await.unwrap().topic,
+        ];
+        topics.sort();
+        assert_eq!(topics, vec!["audit", "orders"]);
+    }
+
+    #[test]
+    fn maps_gateway_topics() {
+        let mut event = event();
+        event.origin = Some("eu".to_string());
+        event
+            .metadata
+            .insert("site".to_string(), "plant#1".to_string());
+        assert_eq!(
+            MqttTopicMapping::default()
+                .apply("devices.telemetry", &event)
+                .unwrap()
+                .as_str(),
+            "devices.telemetry"
+        );
+
+        let mapping: MqttTopicMapping =
+            serde_json::from_str(r#"{"dotsToSlashes": true, "prefix": "site/{origin}/"}"#).unwrap();
+        assert_eq!(
+            mapping
+                .apply("devices.telemetry.temp", &event)
+                .unwrap()
+                .as_str(),
+            "site/eu/devices/telemetry/temp"
+        );
+
+        let mapping = MqttTopicMapping {
+            dots_to_slashes: false,
+            prefix: Some(TopicTemplate::new("{metadata.site}/").unwrap()),
+        };
+        let error = mapping.apply("devices", &event).unwrap_err();
+        assert!(error.to_string().contains("wildcards"));
+    }
+
+    #[tokio::test]
+    async fn fails_without_broker_acknowledgment() {
+        let (port, mut received) = broker(false).await;
+        let publisher = MqttPublisher::new(config(
+            port,
+            QosLevel::AtLeastOnce,
+            Duration::from_millis(200),
+        ))
+        .unwrap();
+        let error = publisher
+            .publish_one("orders", event(), PublishContext::default())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("did not acknowledge"));
+        assert_eq!(received.recv().await.unwrap().topic, "orders");
+    }
+
+    #[tokio::test]
+    async fn publishes_mqtt5_properties() {
+        use rumqttc::v5::mqttbytes::v5::{ConnAck, ConnectReturnCode, PubAck};
+        use rumqttc::v5::mqttbytes::Error as MqttError;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (received, mut packets) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = BytesMut::new();
+            loop {
+                let packet = match PacketV5::read(&mut buffer, None) {
+                    Ok(packet) => packet,
+                    Err(MqttError::InsufficientBytes(_)) => {
+                        if socket.read_buf(&mut buffer).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        continue;
+                    }
+                    Err(e) => panic!("invalid MQTT packet: {e:?}"),
+                };
+                let reply = match &packet {
+                    PacketV5::Connect(..) => Some(PacketV5::ConnAck(ConnAck {
+                        session_present: false,
+                        code: ConnectReturnCode::Success,
+                        properties: None,
+                    })),
+                    PacketV5::Publish(publish) => {
+                        Some(PacketV5::PubAck(PubAck::new(publish.pkid, None)))
+                    }
+                    _ => None,
+                };
+                let _ = received.send(packet);
+                if let Some(reply) = reply {
+                    let mut out = BytesMut::new();
+                    reply.write(&mut out, None).unwrap();
+                    socket.write_all(&out).await.unwrap();
+                }
+            }
+        });
+
+        let mut config = config(port, QosLevel::AtLeastOnce, Duration::from_secs(5));
+        config.protocol_version = MqttVersion::V5;
+        config.username = Some("gateway".to_string());
+        config.password = Some("secret".to_string());
+        config.last_will = Some(MqttLastWillConfig {
+            topic: "gateway/status".to_string(),
+            payload: "offline".to_string(),
+            qos: QosLevel::AtLeastOnce,
+            retain: true,
+        });
+        config.message_expiry = Some(Duration::from_secs(60));
+        let publisher = MqttPublisher::new(config).unwrap();
+        let mut event = event();
+        event
+            .metadata
+            .insert("tenant_id".to_string(), "acme".to_string());
+        publisher
+            .publish_one("orders", event.clone(), PublishContext::default())
             .await
-            .map_err(|e| PublisherError::Generic(e.to_string()))?;
+            .unwrap();
 
-        Ok(())
+        let Some(PacketV5::Connect(_, Some(will), Some(login))) = packets.recv().await else {
+            panic!("expected CONNECT with last will and credentials");
+        };
+        assert_eq!(will.topic, "gateway/status");
+        assert_eq!(login.username, "gateway");
+        assert_eq!(login.password, "secret");
+        let Some(PacketV5::Publish(publish)) = packets.recv().await else {
+            panic!("expected PUBLISH");
+        };
+        let properties = publish.properties.unwrap();
+        assert_eq!(properties.content_type.as_deref(), Some("application/json"));
+        assert_eq!(properties.message_expiry_interval, Some(60));
+        assert_eq!(
+            properties.user_properties,
+            vec![
+                ("event_id".to_string(), event.id.to_string()),
+                ("event_type".to_string(), "order.created".to_string()),
+                ("tenant_id".to_string(), "acme".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let mut config = config(1883, QosLevel::AtLeastOnce, Duration::from_secs(5));
+        config.message_expiry = Some(Duration::from_secs(60));
+        assert!(MqttPublisher::new(config.clone())
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("requires protocol_version"));
+
+        config.message_expiry = None;
+        config.password = Some("secret".to_string());
+        assert!(MqttPublisher::new(config.clone()).is_err());
+
+        config.password = None;
+        config.tls = Some(MqttTlsConfig {
+            ca_location: None,
+            certificate_location: Some("/etc/mqtt/client.pem".to_string()),
+            key_location: None,
+        });
+        assert!(MqttPublisher::new(config).is_err());
     }
 }
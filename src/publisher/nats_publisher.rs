@@ -0,0 +1,519 @@
+use crate::model::event::Event;
+use crate::model::topic::TopicNaming;
+use crate::publisher::format::{Encoder, EncodingConfig};
+use crate::publisher::publisher::{PublishContext, PublishRequest, Publisher, PublisherError};
+use async_nats::connection::State;
+use async_nats::header::{HeaderMap, HeaderName, NATS_MESSAGE_ID};
+use async_nats::jetstream::{self, context::PublishAckFuture};
+use async_nats::{Client, ConnectOptions, ServerAddr};
+use async_trait::async_trait;
+use bytes::Bytes;
+use duration_str::deserialize_duration;
+use futures::future::join_all;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::time;
+
+/// Prefix of the headers that NATS and JetStream interpret, such as
+/// `Nats-Msg-Id`.
+const NATS_PREFIX: &str = "Nats-";
+
+/// How a publish is confirmed.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NatsMode {
+    /// Core NATS: the message was flushed to the server.
+    #[default]
+    Core,
+    /// JetStream: a stream stored the message and acknowledged it.
+    JetStream,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NatsPublisherConfig {
+    pub servers: Vec<String>,
+    #[serde(default)]
+    pub mode: NatsMode,
+    /// Connection name shown by the server.
+    pub client_name: Option<String>,
+    /// How long a publish waits for the server's flush or JetStream ack.
+    #[serde(
+        default = "default_ack_timeout",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub ack_timeout: Duration,
+    pub token: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Seed of the NKey the client signs the server's nonce with.
+    pub nkey_seed: Option<String>,
+    #[serde(flatten)]
+    pub encoding: EncodingConfig,
+}
+
+fn default_ack_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn invalid(message: impl Into<String>) -> PublisherError {
+    PublisherError::Generic(format!("invalid NATS settings: {}", message.into()))
+}
+
+fn publish_error(topic: &str, error: impl std::fmt::Display) -> PublisherError {
+    PublisherError::Generic(format!(
+        "failed to publish to NATS subject '{topic}': {error}"
+    ))
+}
+
+pub struct NatsPublisher {
+    client: Client,
+    /// Set in JetStream mode.
+    jetstream: Option<jetstream::Context>,
+    ack_timeout: Duration,
+    encoder: Encoder,
+}
+
+impl NatsPublisherConfig {
+    fn connect_options(&self) -> Result<ConnectOptions, PublisherError> {
+        let mut options = ConnectOptions::new().retry_on_initial_connect();
+        if let Some(name) = &self.client_name {
+            options = options.name(name);
+        }
+        match (&self.token, &self.username, &self.nkey_seed) {
+            (None, None, None) if self.password.is_some() => {
+                Err(invalid("password requires username"))
+            }
+            (None, None, None) => Ok(options),
+            (Some(token), None, None) => Ok(options.token(token.clone())),
+            (None, Some(username), None) => Ok(options
+                .user_and_password(username.clone(), self.password.clone().unwrap_or_default())),
+            (None, None, Some(seed)) => Ok(options.nkey(seed.clone())),
+            _ => Err(invalid(
+                "token, username and nkey_seed are mutually exclusive",
+            )),
+        }
+    }
+}
+
+impl NatsPublisher {
+    /// Creates the publisher. The connection is established in the
+    /// background, so the gateway starts while NATS is unavailable.
+    pub async fn new(config: NatsPublisherConfig) -> Result<Self, PublisherError> {
+        if config.servers.is_empty() {
+            return Err(invalid("servers cannot be empty"));
+        }
+        let servers = config
+            .servers
+            .iter()
+            .map(|server| {
+                ServerAddr::from_str(server)
+                    .map_err(|e| invalid(format!("invalid server '{server}': {e}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let client = config
+            .connect_options()?
+            .connect(servers)
+            .await
+            .map_err(|e| PublisherError::Generic(format!("failed to connect to NATS: {e}")))?;
+
+        let jetstream = (config.mode == NatsMode::JetStream).then(|| {
+            let mut context = jetstream::new(client.clone());
+            context.set_timeout(config.ack_timeout);
+            context
+        });
+        Ok(NatsPublisher {
+            client,
+            jetstream,
+            ack_timeout: config.ack_timeout,
+            encoder: Encoder::new(config.encoding),
+        })
+    }
+
+    /// Message headers: `Nats-Msg-Id`, which JetStream deduplicates on, the
+    /// event id, type and version, metadata and the encoded headers. Metadata
+    /// that is not a valid NATS header, or uses the reserved `Nats-` prefix,
+    /// is left out.
+    ///
+    /// The message id names the subject too: a stream capturing several
+    /// subjects must keep each copy of a fanned-out event.
+    fn headers(event: &Event, topic: &str, encoded: Vec<(String, String)>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("event_id", event.id.to_string());
+        headers.insert("event_type", event.event_type.as_str());
+        if let Some(version) = &event.event_version {
+            headers.insert("event_version", version.as_str());
+        }
+        let mut metadata: Vec<_> = event
+            .metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        metadata.sort();
+        for (name, value) in metadata.into_iter().chain(encoded) {
+            let reserved = name
+                .get(..NATS_PREFIX.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(NATS_PREFIX));
+            let Ok(name) = HeaderName::from_str(&name) else {
+                continue;
+            };
+            if !reserved && !value.contains(['\r', '\n']) {
+                headers.insert(name, value);
+            }
+        }
+        headers.insert(NATS_MESSAGE_ID, format!("{}:{topic}", event.id));
+        headers
+    }
+
+    fn message(
+        &self,
+        topic: &str,
+        event: &Event,
+        context: &PublishContext,
+    ) -> Result<(HeaderMap, Bytes), PublisherError> {
+        let encoded = self.encoder.encode(event, context)?;
+        Ok((
+            Self::headers(event, topic, encoded.headers),
+            Bytes::from(encoded.payload.into_bytes()),
+        ))
+    }
+
+    /// Hands a message to the client; in JetStream mode, returns its pending
+    /// acknowledgment.
+    async fn send(
+        &self,
+        topic: &str,
+        event: &Event,
+        context: &PublishContext,
+    ) -> Result<Option<PublishAckFuture>, PublisherError> {
+        let (headers, payload) = self.message(topic, event, context)?;
+        match &self.jetstream {
+            Some(jetstream) => jetstream
+                .publish_with_headers(topic.to_string(), headers, payload)
+                .await
+                .map(Some)
+                .map_err(|e| publish_error(topic, e)),
+            None => self
+                .client
+                .publish_with_headers(topic.to_string(), headers, payload)
+                .await
+                .map(|_| None)
+                .map_err(|e| publish_error(topic, e)),
+        }
+    }
+
+    /// Waits until the server has received every message sent so far.
+    async fn flush(&self) -> Result<(), String> {
+        match time::timeout(self.ack_timeout, self.client.flush()).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(_) => Err(format!(
+                "NATS server did not confirm the flush within {:?}",
+                self.ack_timeout
+            )),
+        }
+    }
+}
+
+async fn acknowledged(topic: &str, ack: PublishAckFuture) -> Result<(), PublisherError> {
+    ack.await.map(|_| ()).map_err(|e| publish_error(topic, e))
+}
+
+#[async_trait]
+impl Publisher<Event> for NatsPublisher {
+    async fn publish_one(
+        &self,
+        topic: &str,
+        payload: Event,
+        context: PublishContext,
+    ) -> Result<(), PublisherError> {
+        match self.send(topic, &payload, &context).await? {
+            Some(ack) => acknowledged(topic, ack).await,
+            None => self.flush().await.map_err(|e| publish_error(topic, e)),
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.connection_state() == State::Connected
+    }
+
+    fn topic_naming(&self) -> TopicNaming {
+        TopicNaming::Nats
+    }
+
+    /// Sends the whole batch before waiting: JetStream acks are awaited
+    /// together, and core messages are confirmed by a single flush.
+    async fn publish_batch(
+        &self,
+        batch: Vec<PublishRequest<Event>>,
+    ) -> Vec<Result<(), PublisherError>> {
+        let mut sent = Vec::with_capacity(batch.len());
+        for request in &batch {
+            sent.push(
+                self.send(&request.topic, &request.payload, &request.context)
+                    .await,
+            );
+        }
+
+        if self.jetstream.is_some() {
+            return join_all(
+                sent.into_iter()
+                    .zip(&batch)
+                    .map(|(sent, request)| async move {
+                        match sent? {
+                            Some(ack) => acknowledged(&request.topic, ack).await,
+                            None => Ok(()),
+                        }
+                    }),
+            )
+            .await;
+        }
+
+        let flushed = self.flush().await;
+        sent.into_iter()
+            .zip(&batch)
+            .map(|(sent, request)| {
+                sent?;
+                flushed
+                    .clone()
+                    .map_err(|e| publish_error(&request.topic, e))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::Data;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    enum Frame {
+        Connect(Value),
+        Publish {
+            subject: String,
+            headers: String,
+            payload: Vec<u8>,
+        },
+    }
+
+    /// Minimal NATS server: answers pings, forwards CONNECT and published
+    /// messages and, when `ack` is set, replies to every publish with it, as
+    /// a JetStream stream does.
+    async fn server(ack: Option<&'static str>) -> (u16, mpsc::UnboundedReceiver<Frame>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (frames, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut reader = BufReader::new(reader);
+            let info = format!(
+                "INFO {{\"server_id\":\"test\",\"server_name\":\"test\",\"version\":\"2.10.0\",\
+                 \"go\":\"go1.22\",\"host\":\"127.0.0.1\",\"port\":{port},\"headers\":true,\
+                 \"max_payload\":1048576,\"proto\":1}}\r\n"
+            );
+            writer.write_all(info.as_bytes()).await.unwrap();
+            let mut subscriptions: Vec<(String, String)> = Vec::new();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                let mut words = line.split_whitespace();
+                match words.next() {
+                    Some("CONNECT") => {
+                        let options = line.trim_end()["CONNECT ".len()..].to_string();
+                        let _ =
+                            frames.send(Frame::Connect(serde_json::from_str(&options).unwrap()));
+                    }
+                    Some("PING") => writer.write_all(b"PONG\r\n").await.unwrap(),
+                    Some("SUB") => {
+                        let words: Vec<&str> = words.collect();
+                        subscriptions
+                            .push((words[0].to_string(), words[words.len() - 1].to_string()));
+                    }
+                    Some(operation @ ("PUB" | "HPUB")) => {
+                        let words: Vec<&str> = words.collect();
+                        let lengths = if operation == "HPUB" { 2 } else { 1 };
+                        let subject = words[0].to_string();
+                        let reply = (words.len() > lengths + 1).then(|| words[1].to_string());
+                        let total: usize = words[words.len() - 1].parse().unwrap();
+                        let header_length: usize = if operation == "HPUB" {
+                            words[words.len() - 2].parse().unwrap()
+                        } else {
+                            0
+                        };
+                        let mut message = vec![0; total + 2];
+                        reader.read_exact(&mut message).await.unwrap();
+                        let _ = frames.send(Frame::Publish {
+                            subject,
+                            headers: String::from_utf8_lossy(&message[..header_length])
+                                .into_owned(),
+                            payload: message[header_length..total].to_vec(),
+                        });
+                        let (Some(reply), Some(ack)) = (reply, ack) else {
+                            continue;
+                        };
+                        let Some((_, sid)) = subscriptions
+                            .iter()
+                            .find(|(pattern, _)| reply.starts_with(pattern.trim_end_matches('*')))
+                        else {
+                            continue;
+                        };
+                        let response = format!("MSG {reply} {sid} {}\r\n{ack}\r\n", ack.len());
+                        writer.write_all(response.as_bytes()).await.unwrap();
+                    }
+                    _ => {}
+                }
+            }
+        });
+        (port, received)
+    }
+
+    fn config(port: u16, mode: NatsMode) -> NatsPublisherConfig {
+        NatsPublisherConfig {
+            servers: vec![format!("nats://127.0.0.1:{port}")],
+            mode,
+            client_name: None,
+            ack_timeout: Duration::from_secs(5),
+            token: None,
+            username: None,
+            password: None,
+            nkey_seed: None,
+            encoding: EncodingConfig::default(),
+        }
+    }
+
+    fn event() -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            metadata: HashMap::from([
+                ("tenant_id".to_string(), "acme".to_string()),
+                ("bad key".to_string(), "dropped".to_string()),
+                ("nats-msg-id".to_string(), "spoofed".to_string()),
+            ]),
+            transport_metadata: None,
+            data_type: None,
+            data: Data::String("created".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        }
+    }
+
+    fn request(event: Event) -> PublishRequest<Event> {
+        PublishRequest {
+            topic: "orders.created".to_string(),
+            payload: event,
+            context: PublishContext::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_core_messages_with_headers() {
+        let (port, mut frames) = server(None).await;
+        let mut config = config(port, NatsMode::Core);
+        config.token = Some("secret".to_string());
+        let publisher = NatsPublisher::new(config).await.unwrap();
+        let event = event();
+        publisher
+            .publish_one("orders.created", event.clone(), PublishContext::default())
+            .await
+            .unwrap();
+        assert!(publisher.is_connected());
+
+        let Some(Frame::Connect(options)) = frames.recv().await else {
+            panic!("expected CONNECT");
+        };
+        assert_eq!(options["auth_token"], "secret");
+        let Some(Frame::Publish {
+            subject,
+            headers,
+            payload,
+        }) = frames.recv().await
+        else {
+            panic!("expected HPUB");
+        };
+        assert_eq!(subject, "orders.created");
+        assert!(headers.contains(&format!("Nats-Msg-Id: {}:orders.created\r\n", event.id)));
+        assert!(headers.contains("event_type: order.created\r\n"));
+        assert!(headers.contains("tenant_id: acme\r\n"));
+        assert!(!headers.contains("dropped"));
+        assert!(!headers.contains("spoofed"));
+        let published: Event = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(published.id, event.id);
+    }
+
+    #[tokio::test]
+    async fn scopes_message_ids_to_the_subject() {
+        let (port, mut frames) = server(None).await;
+        let publisher = NatsPublisher::new(config(port, NatsMode::Core))
+            .await
+            .unwrap();
+        let event = event();
+        let mut audit = request(event.clone());
+        audit.topic = "orders.audit".to_string();
+        let results = publisher
+            .publish_batch(vec![request(event.clone()), audit])
+            .await;
+        assert!(results.iter().all(Result::is_ok));
+
+        let mut ids = Vec::new();
+        while ids.len() < 2 {
+            if let Some(Frame::Publish {
+                subject, headers, ..
+            }) = frames.recv().await
+            {
+                assert!(headers.contains(&format!("Nats-Msg-Id: {}:{subject}\r\n", event.id)));
+                ids.push(subject);
+            }
+        }
+        assert_eq!(ids, vec!["orders.created", "orders.audit"]);
+    }
+
+    #[tokio::test]
+    async fn awaits_jetstream_acks() {
+        let (port, _frames) = server(Some(r#"{"stream":"EVENTS","seq":1}"#)).await;
+        let publisher = NatsPublisher::new(config(port, NatsMode::JetStream))
+            .await
+            .unwrap();
+        let results = publisher
+            .publish_batch(vec![request(event()), request(event())])
+            .await;
+        assert!(results.iter().all(Result::is_ok));
+
+        let (port, _frames) = server(Some(
+            r#"{"error":{"code":503,"err_code":10039,"description":"jetstream not enabled"}}"#,
+        ))
+        .await;
+        let publisher = NatsPublisher::new(config(port, NatsMode::JetStream))
+            .await
+            .unwrap();
+        let error = publisher
+            .publish_one("orders.created", event(), PublishContext::default())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("orders.created"));
+    }
+
+    #[tokio::test]
+    async fn rejects_conflicting_credentials() {
+        let mut config = config(4222, NatsMode::Core);
+        config.token = Some("secret".to_string());
+        config.username = Some("gateway".to_string());
+        assert!(NatsPublisher::new(config.clone()).await.is_err());
+
+        config.token = None;
+        config.username = None;
+        config.password = Some("secret".to_string());
+        assert!(NatsPublisher::new(config).await.is_err());
+    }
+}
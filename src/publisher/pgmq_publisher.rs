@@ -1,9 +1,12 @@
 use crate::model::event::Event;
-use crate::publisher::publisher::{PublishContext, Publisher, PublisherError};
+use crate::model::topic::TopicNaming;
+use crate::publisher::format::{Encoder, EncodingConfig};
+use crate::publisher::publisher::{PublishContext, PublishRequest, Publisher, PublisherError};
 use async_trait::async_trait;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::collections::HashMap;
 
 const DEFAULT_MAX_CONNECTIONS: u32 = 10;
 
@@ -16,6 +19,8 @@ pub struct PgmqPublisherConfig {
     pub delay_seconds: i32,
     #[serde(default)]
     pub group_metadata_field: Option<String>,
+    #[serde(flatten)]
+    pub encoding: EncodingConfig,
 }
 
 fn default_max_connections() -> u32 {
@@ -33,6 +38,7 @@ pub struct PgmqPublisher {
     pool: PgPool,
     delay_seconds: i32,
     group_metadata_field: Option<String>,
+    encoder: Encoder,
 }
 
 impl PgmqPublisher {
@@ -51,6 +57,7 @@ impl PgmqPublisher {
             pool,
             delay_seconds: config.delay_seconds,
             group_metadata_field: config.group_metadata_field,
+            encoder: Encoder::new(config.encoding),
         })
     }
 
@@ -87,6 +94,23 @@ impl PgmqPublisher {
 
         Ok(headers)
     }
+
+    fn message(
+        &self,
+        event: &Event,
+        context: &PublishContext,
+    ) -> Result<(Value, Value), PublisherError> {
+        let encoded = self.encoder.encode(event, context)?;
+        let group_metadata_field = select_group_metadata_field(
+            context.group_metadata_field.as_deref(),
+            self.group_metadata_field.as_deref(),
+        );
+        let mut headers = Self::headers(event, group_metadata_field)?;
+        for (name, value) in encoded.headers {
+            headers[name] = Value::String(value);
+        }
+        Ok((encoded.payload.into_json(), headers))
+    }
 }
 
 impl PgmqPublisherConfig {
@@ -127,13 +151,7 @@ impl Publisher<Event> for PgmqPublisher {
         event: Event,
         context: PublishContext,
     ) -> Result<(), PublisherError> {
-        let message = serde_json::to_value(&event)
-            .map_err(|error| PublisherError::Generic(error.to_string()))?;
-        let group_metadata_field = select_group_metadata_field(
-            context.group_metadata_field.as_deref(),
-            self.group_metadata_field.as_deref(),
-        );
-        let headers = Self::headers(&event, group_metadata_field)?;
+        let (message, headers) = self.message(&event, &context)?;
 
         sqlx::query_scalar::<_, i64>(
             "SELECT pgmq.send($1::text, $2::jsonb, $3::jsonb, $4::integer)",
@@ -151,6 +169,55 @@ impl Publisher<Event> for PgmqPublisher {
             ))
         })
     }
+
+    fn topic_naming(&self) -> TopicNaming {
+        TopicNaming::Pgmq
+    }
+
+    async fn publish_batch(
+        &self,
+        batch: Vec<PublishRequest<Event>>,
+    ) -> Vec<Result<(), PublisherError>> {
+        let mut results: Vec<Result<(), PublisherError>> = vec![Ok(()); batch.len()];
+        let mut queues: HashMap<&str, Vec<(usize, Value, Value)>> = HashMap::new();
+        for (index, request) in batch.iter().enumerate() {
+            match self.message(&request.payload, &request.context) {
+                Ok((message, headers)) => queues
+                    .entry(request.topic.as_str())
+                    .or_default()
+                    .push((index, message, headers)),
+                Err(error) => results[index] = Err(error),
+            }
+        }
+
+        // One `pgmq.send_batch` per queue; the whole queue batch commits or
+        // fails together.
+        for (queue_name, entries) in queues {
+            let (indexes, (messages, headers)): (Vec<usize>, (Vec<Value>, Vec<Value>)) = entries
+                .into_iter()
+                .map(|(index, message, headers)| (index, (message, headers)))
+                .unzip();
+            let outcome = sqlx::query_scalar::<_, i64>(
+                "SELECT * FROM pgmq.send_batch($1::text, $2::jsonb[], $3::jsonb[], $4::integer)",
+            )
+            .bind(queue_name)
+            .bind(messages)
+            .bind(headers)
+            .bind(self.delay_seconds)
+            .fetch_all(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|error| {
+                PublisherError::Generic(format!(
+                    "failed to enqueue batch in PGMQ queue '{queue_name}': {error}"
+                ))
+            });
+            for index in indexes {
+                results[index] = outcome.clone();
+            }
+        }
+        results
+    }
 }
 
 #[cfg(test)]
@@ -174,6 +241,7 @@ mod tests {
             transport_metadata: None,
             metadata,
             origin: None,
+            ingest: Default::default(),
             timestamp: None,
         }
     }
@@ -217,6 +285,7 @@ mod tests {
         );
         let context = PublishContext {
             group_metadata_field: Some("order_id".to_string()),
+            ..PublishContext::default()
         };
         let global = Some("aggregate_id");
         let selected_field =
@@ -260,6 +329,7 @@ mod tests {
             max_connections: 0,
             delay_seconds: -1,
             group_metadata_field: None,
+            encoding: Default::default(),
         };
 
         assert_eq!(
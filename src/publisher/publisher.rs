@@ -1,9 +1,12 @@
 use crate::model::event::Event;
+use crate::model::publishing::{MqttTopicMapping, OutputFormat, PartitionKey};
+use crate::model::topic::TopicNaming;
 use async_trait::async_trait;
 use log::info;
 use std::fmt;
+use std::num::NonZeroU64;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PublisherError {
     Generic(String),
 }
@@ -21,16 +24,70 @@ impl std::error::Error for PublisherError {}
 #[derive(Clone, Debug, Default)]
 pub struct PublishContext {
     pub group_metadata_field: Option<String>,
+    /// Output format of the routing rule, overriding the publisher's.
+    pub format: Option<OutputFormat>,
+    /// Kafka record key of the routing rule, overriding the publisher's.
+    pub partition_key: Option<PartitionKey>,
+    /// Metadata field holding the Kafka partition, overriding the
+    /// publisher's.
+    pub partition_metadata_field: Option<String>,
+    /// MQTT topic mapping of the routing rule, overriding the publisher's.
+    pub mqtt_topic_mapping: Option<MqttTopicMapping>,
+    /// Redis stream maximum length of the routing rule, overriding the
+    /// publisher's.
+    pub stream_max_length: Option<NonZeroU64>,
+}
+
+/// A single message of a [`Publisher::publish_batch`] call.
+#[derive(Clone, Debug)]
+pub struct PublishRequest<T> {
+    pub topic: String,
+    pub payload: T,
+    pub context: PublishContext,
 }
 
 #[async_trait]
-pub trait Publisher<T>: Send + Sync {
+pub trait Publisher<T: Send + 'static>: Send + Sync {
     async fn publish_one(
         &self,
         topic: &str,
         payload: T,
         context: PublishContext,
     ) -> Result<(), PublisherError>;
+
+    /// Whether the publisher is connected to its broker. Publishers without a
+    /// long-lived connection are always connected.
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    /// Whether messages can be published in `format`.
+    fn supports_format(&self, _format: OutputFormat) -> bool {
+        true
+    }
+
+    /// Naming rules of the broker's topics, checked when routing rules are
+    /// created and when their topics are rendered.
+    fn topic_naming(&self) -> TopicNaming {
+        TopicNaming::Generic
+    }
+
+    /// Publishes several messages and returns one result per request, in
+    /// request order. The default implementation publishes them one by one;
+    /// publishers with a native batch API should override it.
+    async fn publish_batch(
+        &self,
+        batch: Vec<PublishRequest<T>>,
+    ) -> Vec<Result<(), PublisherError>> {
+        let mut results = Vec::with_capacity(batch.len());
+        for request in batch {
+            results.push(
+                self.publish_one(&request.topic, request.payload, request.context)
+                    .await,
+            );
+        }
+        results
+    }
 }
 
 pub struct NoOpPublisher;
@@ -0,0 +1,347 @@
+use crate::model::event::Event;
+use crate::publisher::format::{Encoder, EncodingConfig};
+use crate::publisher::publisher::{PublishContext, PublishRequest, Publisher, PublisherError};
+use async_trait::async_trait;
+use deadpool::managed::{self, Metrics, Pool, RecycleResult};
+use deadpool::Runtime;
+use duration_str::deserialize_duration;
+use futures::future::join_all;
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncConnectionConfig, Client, Cmd, RedisError};
+use serde::Deserialize;
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+const DEFAULT_MAX_CONNECTIONS: usize = 10;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RedisStreamsPublisherConfig {
+    /// Server URL, e.g. `redis://:password@redis:6379/0`.
+    pub url: String,
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+    /// Approximate length streams are trimmed to with `MAXLEN ~`; streams
+    /// grow without bound when unset.
+    #[serde(default)]
+    pub max_length: Option<NonZeroU64>,
+    /// How long connecting, waiting for a pooled connection and each command
+    /// may take.
+    #[serde(default = "default_timeout", deserialize_with = "deserialize_duration")]
+    pub timeout: Duration,
+    #[serde(flatten)]
+    pub encoding: EncodingConfig,
+}
+
+fn default_max_connections() -> usize {
+    DEFAULT_MAX_CONNECTIONS
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn publish_error(topic: &str, error: impl std::fmt::Display) -> PublisherError {
+    PublisherError::Generic(format!(
+        "failed to publish to Redis stream '{topic}': {error}"
+    ))
+}
+
+/// Creates multiplexed connections for the pool and checks them with a
+/// `PING` before reuse.
+struct ConnectionManager {
+    client: Client,
+    config: AsyncConnectionConfig,
+}
+
+impl managed::Manager for ConnectionManager {
+    type Type = MultiplexedConnection;
+    type Error = RedisError;
+
+    async fn create(&self) -> Result<MultiplexedConnection, RedisError> {
+        self.client
+            .get_multiplexed_async_connection_with_config(&self.config)
+            .await
+    }
+
+    async fn recycle(
+        &self,
+        connection: &mut MultiplexedConnection,
+        _: &Metrics,
+    ) -> RecycleResult<RedisError> {
+        redis::cmd("PING").query_async::<()>(connection).await?;
+        Ok(())
+    }
+}
+
+pub struct RedisStreamsPublisher {
+    pool: Pool<ConnectionManager>,
+    max_length: Option<NonZeroU64>,
+    encoder: Encoder,
+}
+
+impl RedisStreamsPublisher {
+    pub async fn new(config: RedisStreamsPublisherConfig) -> Result<Self, PublisherError> {
+        if config.max_connections == 0 {
+            return Err(PublisherError::Generic(
+                "Redis max_connections must be greater than zero".to_string(),
+            ));
+        }
+        let client = Client::open(config.url.as_str())
+            .map_err(|e| PublisherError::Generic(format!("invalid Redis url: {e}")))?;
+        let manager = ConnectionManager {
+            client,
+            config: AsyncConnectionConfig::new()
+                .set_connection_timeout(config.timeout)
+                .set_response_timeout(config.timeout),
+        };
+        let pool = Pool::builder(manager)
+            .max_size(config.max_connections)
+            .wait_timeout(Some(config.timeout))
+            .runtime(Runtime::Tokio1)
+            .build()
+            .map_err(|e| PublisherError::Generic(format!("invalid Redis pool settings: {e}")))?;
+        // Opens the first connection, which returns to the pool.
+        drop(
+            pool.get()
+                .await
+                .map_err(|e| PublisherError::Generic(format!("failed to connect to Redis: {e}")))?,
+        );
+
+        Ok(RedisStreamsPublisher {
+            pool,
+            max_length: config.max_length,
+            encoder: Encoder::new(config.encoding),
+        })
+    }
+
+    async fn connection(&self) -> Result<managed::Object<ConnectionManager>, String> {
+        self.pool.get().await.map_err(|e| e.to_string())
+    }
+
+    /// `XADD` of the event to the stream named `topic`, trimmed to the rule's
+    /// or the publisher's maximum length. The entry holds the event id, type
+    /// and version, the content type, binary-mode attributes and the payload.
+    fn xadd(
+        &self,
+        topic: &str,
+        event: &Event,
+        context: &PublishContext,
+    ) -> Result<Cmd, PublisherError> {
+        let encoded = self.encoder.encode(event, context)?;
+        let content_type = encoded.content_type(self.encoder.format(context));
+
+        let mut command = redis::cmd("XADD");
+        command.arg(topic);
+        if let Some(max_length) = context.stream_max_length.or(self.max_length) {
+            command.arg("MAXLEN").arg("~").arg(max_length.get());
+        }
+        command
+            .arg("*")
+            .arg("event_id")
+            .arg(event.id.to_string())
+            .arg("event_type")
+            .arg(&event.event_type);
+        if let Some(version) = &event.event_version {
+            command.arg("event_version").arg(version);
+        }
+        command.arg("content_type").arg(content_type);
+        for (name, value) in &encoded.headers {
+            if name != "content-type" {
+                command.arg(name).arg(value);
+            }
+        }
+        command.arg("payload").arg(encoded.payload.into_bytes());
+        Ok(command)
+    }
+}
+
+async fn send(
+    topic: &str,
+    command: Result<Cmd, PublisherError>,
+    mut connection: MultiplexedConnection,
+) -> Result<(), PublisherError> {
+    command?
+        .query_async::<String>(&mut connection)
+        .await
+        .map(|_| ())
+        .map_err(|e| publish_error(topic, e))
+}
+
+#[async_trait]
+impl Publisher<Event> for RedisStreamsPublisher {
+    async fn publish_one(
+        &self,
+        topic: &str,
+        payload: Event,
+        context: PublishContext,
+    ) -> Result<(), PublisherError> {
+        let command = self.xadd(topic, &payload, &context);
+        let connection = self
+            .connection()
+            .await
+            .map_err(|e| publish_error(topic, e))?;
+        send(topic, command, connection.clone()).await
+    }
+
+    /// Sends the whole batch over one pooled connection, which pipelines the
+    /// commands, so each entry still succeeds or fails on its own.
+    async fn publish_batch(
+        &self,
+        batch: Vec<PublishRequest<Event>>,
+    ) -> Vec<Result<(), PublisherError>> {
+        let connection = match self.connection().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                return batch
+                    .iter()
+                    .map(|request| Err(publish_error(&request.topic, &e)))
+                    .collect()
+            }
+        };
+        join_all(batch.iter().map(|request| {
+            let command = self.xadd(&request.topic, &request.payload, &request.context);
+            send(&request.topic, command, connection.clone())
+        }))
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::Data;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use uuid::Uuid;
+
+    /// Minimal Redis server: forwards `XADD` arguments and refuses appends to
+    /// the `wrongtype` key, as Redis does for a key that is not a stream.
+    async fn server() -> (u16, mpsc::UnboundedReceiver<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (commands, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let commands = commands.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = socket.into_split();
+                    let mut reader = BufReader::new(reader);
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let count: usize = line.trim_end()[1..].parse().unwrap();
+                        let mut args = Vec::with_capacity(count);
+                        for _ in 0..count {
+                            line.clear();
+                            reader.read_line(&mut line).await.unwrap();
+                            let length: usize = line.trim_end()[1..].parse().unwrap();
+                            let mut arg = vec![0; length + 2];
+                            reader.read_exact(&mut arg).await.unwrap();
+                            args.push(String::from_utf8_lossy(&arg[..length]).into_owned());
+                        }
+                        let reply: &[u8] = match args[0].as_str() {
+                            "XADD" if args[1] == "wrongtype" => {
+                                b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+                            }
+                            "XADD" => b"$3\r\n1-0\r\n",
+                            "PING" => b"+PONG\r\n",
+                            _ => b"+OK\r\n",
+                        };
+                        if args[0] == "XADD" {
+                            let _ = commands.send(args);
+                        }
+                        writer.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        (port, received)
+    }
+
+    fn config(port: u16) -> RedisStreamsPublisherConfig {
+        RedisStreamsPublisherConfig {
+            url: format!("redis://127.0.0.1:{port}"),
+            max_connections: 2,
+            max_length: NonZeroU64::new(1000),
+            timeout: Duration::from_secs(5),
+            encoding: EncodingConfig::default(),
+        }
+    }
+
+    fn event() -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: Some("2".to_string()),
+            metadata: HashMap::new(),
+            transport_metadata: None,
+            data_type: None,
+            data: Data::String("created".to_string()),
+            timestamp: None,
+            origin: None,
+            ingest: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn appends_entries_trimmed_per_rule() {
+        let (port, mut commands) = server().await;
+        let publisher = RedisStreamsPublisher::new(config(port)).await.unwrap();
+        let event = event();
+        let context = PublishContext {
+            stream_max_length: NonZeroU64::new(10),
+            ..PublishContext::default()
+        };
+        publisher
+            .publish_one("orders", event.clone(), context)
+            .await
+            .unwrap();
+
+        let args = commands.recv().await.unwrap();
+        assert_eq!(args[..6], ["XADD", "orders", "MAXLEN", "~", "10", "*"]);
+        assert_eq!(
+            args[6..14],
+            [
+                "event_id",
+                &event.id.to_string(),
+                "event_type",
+                "order.created",
+                "event_version",
+                "2",
+                "content_type",
+                "application/json",
+            ]
+        );
+        assert_eq!(args[14], "payload");
+        let published: Event = serde_json::from_str(&args[15]).unwrap();
+        assert_eq!(published.id, event.id);
+
+        publisher
+            .publish_one("orders", event, PublishContext::default())
+            .await
+            .unwrap();
+        let args = commands.recv().await.unwrap();
+        assert_eq!(args[2..5], ["MAXLEN", "~", "1000"]);
+    }
+
+    #[tokio::test]
+    async fn fails_batch_entries_separately() {
+        let (port, _commands) = server().await;
+        let publisher = RedisStreamsPublisher::new(config(port)).await.unwrap();
+        let batch = ["orders", "wrongtype"].map(|topic| PublishRequest {
+            topic: topic.to_string(),
+            payload: event(),
+            context: PublishContext::default(),
+        });
+        let results = publisher.publish_batch(batch.into()).await;
+        assert!(results[0].is_ok());
+        let error = results[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains("wrongtype") && error.contains("WRONGTYPE"));
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use regex::RegexSet;
+
+use crate::model::{
+    event::Event,
+    expressions::{Condition, StringExpression},
+    routing::TopicRoutingRule,
+};
+
+use super::router::{rule_matches, TopicRouter};
+
+/// Routing index built once from an ordered rule list.
+///
+/// Rules are indexed by their `eventTypeCondition`: `equals` values go into a
+/// hash map, `startsWith` prefixes into a byte trie and regexes into a single
+/// `RegexSet`. An `or` of such expressions is indexed under each of them.
+/// Every other condition is checked for every event. The index only narrows
+/// the candidates; each candidate is still checked in rule order with the
+/// full rule conditions, so results are the same as [`super::router::TopicRoutings`].
+pub struct CompiledRoutings {
+    rules: Vec<TopicRoutingRule>,
+    equals: HashMap<String, Vec<usize>>,
+    prefixes: PrefixTrie,
+    regexes: RegexSet,
+    regex_rules: Vec<usize>,
+    unindexed: Vec<usize>,
+}
+
+#[derive(Default)]
+struct PrefixTrie {
+    rules: Vec<usize>,
+    children: HashMap<u8, PrefixTrie>,
+}
+
+impl PrefixTrie {
+    fn insert(&mut self, prefix: &str, rule: usize) {
+        let node = prefix
+            .bytes()
+            .fold(self, |node, byte| node.children.entry(byte).or_default());
+        node.rules.push(rule);
+    }
+
+    fn collect(&self, value: &str, candidates: &mut Vec<usize>) {
+        let mut node = self;
+        candidates.extend_from_slice(&node.rules);
+        for byte in value.bytes() {
+            match node.children.get(&byte) {
+                Some(child) => node = child,
+                None => return,
+            }
+            candidates.extend_from_slice(&node.rules);
+        }
+    }
+}
+
+/// Indexable parts of an event type condition, or `None` when the rule must
+/// be checked for every event.
+fn index_keys(condition: &Condition) -> Option<Vec<&StringExpression>> {
+    match condition {
+        Condition::ONE(
+            expression @ (StringExpression::Equals { .. }
+            | StringExpression::StartsWith { .. }
+            | StringExpression::RegexMatch { .. }),
+        ) => Some(vec![expression]),
+        Condition::OR(conditions) if !conditions.is_empty() => {
+            let mut keys = Vec::new();
+            for condition in conditions {
+                keys.extend(index_keys(condition)?);
+            }
+            Some(keys)
+        }
+        _ => None,
+    }
+}
+
+impl CompiledRoutings {
+    /// `rules` must already be in routing order.
+    pub fn new(rules: Vec<TopicRoutingRule>) -> Self {
+        let mut equals: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut prefixes = PrefixTrie::default();
+        let mut patterns = Vec::new();
+        let mut regex_rules = Vec::new();
+        let mut unindexed = Vec::new();
+
+        for (index, rule) in rules.iter().enumerate() {
+            let Some(keys) = index_keys(&rule.event_type_condition) else {
+                unindexed.push(index);
+                continue;
+            };
+            for key in keys {
+                match key {
+                    StringExpression::Equals { value } => {
+                        equals.entry(value.clone()).or_default().push(index)
+                    }
+                    StringExpression::StartsWith { value } => prefixes.insert(value, index),
+                    StringExpression::RegexMatch { value } => {
+                        patterns.push(value.as_str().to_string());
+                        regex_rules.push(index);
+                    }
+                    _ => unreachable!("only indexable expressions are returned"),
+                }
+            }
+        }
+
+        let regexes = match RegexSet::new(&patterns) {
+            Ok(regexes) => regexes,
+            Err(error) => {
+                log::warn!("regex routing index unavailable, scanning regex rules: {error}");
+                unindexed.append(&mut regex_rules);
+                unindexed.sort_unstable();
+                unindexed.dedup();
+                RegexSet::empty()
+            }
+        };
+
+        CompiledRoutings {
+            rules,
+            equals,
+            prefixes,
+            regexes,
+            regex_rules,
+            unindexed,
+        }
+    }
+
+    fn candidates(&self, event_type: &str) -> Vec<usize> {
+        let mut candidates = self.unindexed.clone();
+        if let Some(rules) = self.equals.get(event_type) {
+            candidates.extend_from_slice(rules);
+        }
+        self.prefixes.collect(event_type, &mut candidates);
+        candidates.extend(
+            self.regexes
+                .matches(event_type)
+                .into_iter()
+                .map(|pattern| self.regex_rules[pattern]),
+        );
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+}
+
+impl TopicRouter for CompiledRoutings {
+    fn route_all(&self, event: &Event) -> Vec<&TopicRoutingRule> {
+        let mut matched = Vec::new();
+        for index in self.candidates(&event.event_type) {
+            let rule = &self.rules[index];
+            if rule_matches(rule, event) {
+                matched.push(rule);
+                if rule.terminal {
+                    break;
+                }
+            }
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::{event::Data, topic::TopicTemplate};
+    use crate::router::router::TopicRoutings;
+
+    fn rule(topic: &str, terminal: bool, event_type_condition: Condition) -> TopicRoutingRule {
+        TopicRoutingRule {
+            id: Uuid::new_v4(),
+            order: 0,
+            topic: TopicTemplate::new(topic).unwrap(),
+            description: None,
+            group_metadata_field: None,
+            terminal,
+            publisher: None,
+            format: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            mqtt_topic_mapping: None,
+            stream_max_length: None,
+            field_conditions: Default::default(),
+            payload_conditions: Vec::new(),
+            event_version_condition: None,
+            event_type_condition,
+        }
+    }
+
+    fn event(event_type: &str) -> Event {
+        Event {
+            id: Uuid::new_v4(),
+            event_type: event_type.to_string(),
+            event_version: None,
+            data: Data::String("data".to_string()),
+            data_type: None,
+            metadata: Default::default(),
+            transport_metadata: None,
+            origin: None,
+            ingest: Default::default(),
+            timestamp: None,
+        }
+    }
+
+    fn expression(expression: StringExpression) -> Condition {
+        Condition::ONE(expression)
+    }
+
+    #[test]
+    fn matches_linear_routing() {
+        let rules = vec![
+            rule(
+                "orders.created",
+                true,
+                expression(StringExpression::Equals {
+                    value: "order.created".to_string(),
+                }),
+            ),
+            rule(
+                "audit",
+                false,
+                expression(StringExpression::RegexMatch {
+                    value: Regex::new(r"\.(created|deleted)$").unwrap(),
+                }),
+            ),
+            rule(
+                "orders",
+                false,
+                expression(StringExpression::StartsWith {
+                    value: "order.".to_string(),
+                }),
+            ),
+            rule(
+                "order_or_user",
+                true,
+                Condition::OR(vec![
+                    expression(StringExpression::StartsWith {
+                        value: "ord".to_string(),
+                    }),
+                    expression(StringExpression::Equals {
+                        value: "user.created".to_string(),
+                    }),
+                ]),
+            ),
+            rule(
+                "not_test",
+                false,
+                Condition::NOT(Box::new(expression(StringExpression::EndsWith {
+                    value: ".test".to_string(),
+                }))),
+            ),
+            rule("catch_all", true, Condition::ANY),
+        ];
+        let compiled = CompiledRoutings::new(rules.clone());
+        let linear = TopicRoutings { rules };
+
+        for event_type in [
+            "order.created",
+            "order.deleted",
+            "order.updated",
+            "orders",
+            "user.created",
+            "user.deleted",
+            "user.test",
+            "",
+        ] {
+            let event = event(event_type);
+            let expected: Vec<_> = linear.route_all(&event).iter().map(|r| r.id).collect();
+            let actual: Vec<_> = compiled.route_all(&event).iter().map(|r| r.id).collect();
+            assert_eq!(actual, expected, "event type {event_type:?}");
+        }
+    }
+
+    #[test]
+    fn empty_rules_route_nothing() {
+        let compiled = CompiledRoutings::new(Vec::new());
+        assert!(compiled.route_all(&event("order.created")).is_empty());
+    }
+}
@@ -1 +1,2 @@
+pub mod compiled;
 pub mod router;
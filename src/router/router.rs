@@ -1,27 +1,78 @@
-use crate::model::{event::Event, routing::TopicRoutingRule};
+use crate::model::{
+    event::Event, expressions::Condition, field::EventField, routing::TopicRoutingRule,
+};
 
 pub struct TopicRoutings {
     pub rules: Vec<TopicRoutingRule>,
 }
 
 pub trait TopicRouter {
-    fn route(&self, event: &Event) -> Option<&TopicRoutingRule>;
+    /// Returns every matching rule in order, stopping after the first
+    /// matching rule marked as `terminal`.
+    fn route_all(&self, event: &Event) -> Vec<&TopicRoutingRule>;
+}
+
+fn version_matches(rule: &TopicRoutingRule, event: &Event) -> bool {
+    match (&rule.event_version_condition, &event.event_version) {
+        (Some(c), Some(v)) => c.matches(v),
+        (None, _) => true,
+        _ => false,
+    }
+}
+
+fn field_matches(field: &EventField, condition: &Condition, event: &Event) -> bool {
+    field
+        .resolve(event)
+        .is_some_and(|value| condition.matches(value))
+}
+
+pub(crate) fn rule_matches(rule: &TopicRoutingRule, event: &Event) -> bool {
+    rule.event_type_condition.matches(&event.event_type)
+        && version_matches(rule, event)
+        && rule
+            .field_conditions
+            .iter()
+            .all(|(field, condition)| field_matches(field, condition, event))
+        && rule
+            .payload_conditions
+            .iter()
+            .all(|condition| condition.matches(&event.data))
+}
+
+/// Names of the rule conditions `event` does not satisfy, in the order they
+/// are checked; empty when the rule matches. Field and payload conditions are
+/// named after their field and JSON Pointer, e.g. `fieldConditions.origin`.
+pub fn failed_conditions(rule: &TopicRoutingRule, event: &Event) -> Vec<String> {
+    let mut failed = Vec::new();
+    if !rule.event_type_condition.matches(&event.event_type) {
+        failed.push("eventTypeCondition".to_string());
+    }
+    if !version_matches(rule, event) {
+        failed.push("eventVersionCondition".to_string());
+    }
+    for (field, condition) in &rule.field_conditions {
+        if !field_matches(field, condition, event) {
+            failed.push(format!("fieldConditions.{field}"));
+        }
+    }
+    for condition in &rule.payload_conditions {
+        if !condition.matches(&event.data) {
+            failed.push(format!("payloadConditions.{}", condition.pointer));
+        }
+    }
+    failed
 }
 
 impl TopicRouter for TopicRoutings {
-    fn route(&self, event: &Event) -> Option<&TopicRoutingRule> {
-        for rule in &self.rules {
-            let type_match = rule.event_type_condition.matches(&event.event_type);
-            let version_match = match (&rule.event_version_condition, &event.event_version) {
-                (Some(c), Some(v)) => c.matches(v),
-                (None, _) => true,
-                _ => false,
-            };
-            if type_match && version_match {
-                return Some(rule);
+    fn route_all(&self, event: &Event) -> Vec<&TopicRoutingRule> {
+        let mut matched = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule_matches(rule, event)) {
+            matched.push(rule);
+            if rule.terminal {
+                break;
             }
         }
-        None
+        matched
     }
 }
 
@@ -31,8 +82,10 @@ mod tests {
 
     use super::*;
     use crate::model::{
-        event::Data, event::Event, expressions::Condition, expressions::StringExpression,
-        topic::Topic,
+        event::Data,
+        event::Event,
+        expressions::{Condition, PayloadCondition, PayloadMatch, StringExpression},
+        topic::TopicTemplate,
     };
 
     #[test]
@@ -42,9 +95,18 @@ mod tests {
                 TopicRoutingRule {
                     id: Uuid::new_v4(),
                     order: 0,
-                    topic: Topic::new("topic_one").unwrap(),
+                    topic: TopicTemplate::new("topic_one").unwrap(),
                     description: None,
                     group_metadata_field: None,
+                    terminal: true,
+                    publisher: None,
+                    format: None,
+                    partition_key: None,
+                    partition_metadata_field: None,
+                    mqtt_topic_mapping: None,
+                    stream_max_length: None,
+                    payload_conditions: Vec::new(),
+                    field_conditions: Default::default(),
                     event_version_condition: None,
                     event_type_condition: Condition::ONE(StringExpression::Equals {
                         value: "event_one".to_string(),
@@ -53,9 +115,18 @@ mod tests {
                 TopicRoutingRule {
                     id: Uuid::new_v4(),
                     order: 0,
-                    topic: Topic::new("topic_two").unwrap(),
+                    topic: TopicTemplate::new("topic_two").unwrap(),
                     description: None,
                     group_metadata_field: None,
+                    terminal: true,
+                    publisher: None,
+                    format: None,
+                    partition_key: None,
+                    partition_metadata_field: None,
+                    mqtt_topic_mapping: None,
+                    stream_max_length: None,
+                    payload_conditions: Vec::new(),
+                    field_conditions: Default::default(),
                     event_version_condition: None,
                     event_type_condition: Condition::ONE(StringExpression::Equals {
                         value: "event_two".to_string(),
@@ -72,6 +143,7 @@ mod tests {
             transport_metadata: None,
             metadata: Default::default(),
             origin: None,
+            ingest: Default::default(),
             timestamp: None,
         };
 
@@ -87,17 +159,19 @@ mod tests {
 
         assert_eq!(
             routings
-                .route(&event)
-                .map(|r| r.topic.clone().into_string()),
+                .route_all(&event)
+                .first()
+                .map(|r| r.topic.to_string()),
             Some("topic_one".to_string())
         );
         assert_eq!(
             routings
-                .route(&event_two)
-                .map(|r| r.topic.clone().into_string()),
+                .route_all(&event_two)
+                .first()
+                .map(|r| r.topic.to_string()),
             Some("topic_two".to_string())
         );
-        assert_eq!(routings.route(&event_three), None);
+        assert!(routings.route_all(&event_three).is_empty());
     }
 
     #[test]
@@ -106,9 +180,18 @@ mod tests {
             rules: vec![TopicRoutingRule {
                 id: Uuid::new_v4(),
                 order: 0,
-                topic: Topic::new("topic").unwrap(),
+                topic: TopicTemplate::new("topic").unwrap(),
                 description: None,
                 group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                payload_conditions: Vec::new(),
+                field_conditions: Default::default(),
                 event_version_condition: Some(Condition::ONE(StringExpression::Equals {
                     value: "1.0".to_string(),
                 })),
@@ -126,6 +209,7 @@ mod tests {
             metadata: Default::default(),
             transport_metadata: None,
             origin: None,
+            ingest: Default::default(),
             timestamp: None,
         };
 
@@ -140,13 +224,219 @@ mod tests {
             ..event.clone()
         };
 
-        assert_eq!(routings.route(&event), None);
+        assert!(routings.route_all(&event).is_empty());
         assert_eq!(
             routings
-                .route(&event_two)
-                .map(|r| r.topic.clone().into_string()),
+                .route_all(&event_two)
+                .first()
+                .map(|r| r.topic.to_string()),
             Some("topic".to_string())
         );
-        assert_eq!(routings.route(&event_three), None);
+        assert!(routings.route_all(&event_three).is_empty());
+    }
+
+    #[test]
+    fn test_topic_router_fans_out_until_terminal_rule() {
+        let rule = |topic: &str, terminal: bool| TopicRoutingRule {
+            id: Uuid::new_v4(),
+            order: 0,
+            topic: TopicTemplate::new(topic).unwrap(),
+            description: None,
+            group_metadata_field: None,
+            terminal,
+            publisher: None,
+            format: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            mqtt_topic_mapping: None,
+            stream_max_length: None,
+            payload_conditions: Vec::new(),
+            field_conditions: Default::default(),
+            event_version_condition: None,
+            event_type_condition: Condition::ONE(StringExpression::StartsWith {
+                value: "order.".to_string(),
+            }),
+        };
+        let routings = TopicRoutings {
+            rules: vec![
+                rule("analytics.orders", false),
+                rule("orders", true),
+                rule("unreachable", true),
+            ],
+        };
+        let event = Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            data: Data::String("".to_string()),
+            data_type: None,
+            metadata: Default::default(),
+            transport_metadata: None,
+            origin: None,
+            ingest: Default::default(),
+            timestamp: None,
+        };
+
+        assert_eq!(
+            routings
+                .route_all(&event)
+                .iter()
+                .map(|r| r.topic.as_str())
+                .collect::<Vec<_>>(),
+            vec!["analytics.orders", "orders"]
+        );
+    }
+
+    #[test]
+    fn test_topic_router_with_field_conditions() {
+        let routings = TopicRoutings {
+            rules: vec![TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("acme.orders").unwrap(),
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                payload_conditions: Vec::new(),
+                field_conditions: [
+                    (
+                        "metadata.tenant_id".parse().unwrap(),
+                        Condition::ONE(StringExpression::Equals {
+                            value: "acme".to_string(),
+                        }),
+                    ),
+                    (
+                        "transportMetadata.jwt_sub".parse().unwrap(),
+                        Condition::ONE(StringExpression::StartsWith {
+                            value: "service-".to_string(),
+                        }),
+                    ),
+                ]
+                .into(),
+                event_version_condition: None,
+                event_type_condition: Condition::ANY,
+            }],
+        };
+        let event = Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            data: Data::String("".to_string()),
+            data_type: None,
+            metadata: [("tenant_id".to_string(), "acme".to_string())].into(),
+            transport_metadata: Some([("jwt_sub".to_string(), "service-a".to_string())].into()),
+            origin: None,
+            ingest: Default::default(),
+            timestamp: None,
+        };
+        let other_tenant = Event {
+            metadata: [("tenant_id".to_string(), "globex".to_string())].into(),
+            ..event.clone()
+        };
+        let anonymous = Event {
+            transport_metadata: None,
+            ..event.clone()
+        };
+
+        assert_eq!(routings.route_all(&event).len(), 1);
+        assert!(routings.route_all(&other_tenant).is_empty());
+        assert!(routings.route_all(&anonymous).is_empty());
+    }
+
+    #[test]
+    fn test_topic_router_with_payload_conditions() {
+        let routings = TopicRoutings {
+            rules: vec![TopicRoutingRule {
+                id: Uuid::new_v4(),
+                order: 0,
+                topic: TopicTemplate::new("orders.de").unwrap(),
+                description: None,
+                group_metadata_field: None,
+                terminal: true,
+                publisher: None,
+                format: None,
+                partition_key: None,
+                partition_metadata_field: None,
+                mqtt_topic_mapping: None,
+                stream_max_length: None,
+                field_conditions: Default::default(),
+                payload_conditions: vec![PayloadCondition {
+                    pointer: "/order/country".to_string(),
+                    condition: PayloadMatch::String(Condition::ONE(StringExpression::Equals {
+                        value: "DE".to_string(),
+                    })),
+                }],
+                event_version_condition: None,
+                event_type_condition: Condition::ANY,
+            }],
+        };
+        let event = Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: None,
+            data: Data::Json(
+                serde_json::json!({"order": {"country": "DE"}})
+                    .as_object()
+                    .unwrap()
+                    .clone()
+                    .into_iter()
+                    .collect(),
+            ),
+            data_type: None,
+            metadata: Default::default(),
+            transport_metadata: None,
+            origin: None,
+            ingest: Default::default(),
+            timestamp: None,
+        };
+        let string_data = Event {
+            data: Data::String(r#"{"order": {"country": "DE"}}"#.to_string()),
+            ..event.clone()
+        };
+
+        assert_eq!(routings.route_all(&event).len(), 1);
+        assert!(routings.route_all(&string_data).is_empty());
+    }
+
+    #[test]
+    fn reports_failed_conditions() {
+        let rule: TopicRoutingRule = serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "order": 0,
+            "topic": "orders",
+            "eventTypeCondition": {"type": "startsWith", "value": "order."},
+            "eventVersionCondition": {"type": "equals", "value": "1.0"},
+            "fieldConditions": {"origin": {"type": "equals", "value": "shop"}},
+            "payloadConditions": [
+                {"pointer": "/total", "condition": {"type": "greaterThan", "value": 100}}
+            ],
+            "description": null,
+            "groupMetadataField": null
+        }))
+        .unwrap();
+        let event = Event {
+            id: Uuid::new_v4(),
+            event_type: "order.created".to_string(),
+            event_version: Some("2.0".to_string()),
+            data: Data::String("".to_string()),
+            data_type: None,
+            transport_metadata: None,
+            metadata: Default::default(),
+            origin: Some("shop".to_string()),
+            ingest: Default::default(),
+            timestamp: None,
+        };
+
+        assert_eq!(
+            failed_conditions(&rule, &event),
+            vec!["eventVersionCondition", "payloadConditions./total"]
+        );
+        assert!(!rule_matches(&rule, &event));
     }
 }
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use log::{debug, info, warn};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use tokio::time;
@@ -19,6 +20,9 @@ pub struct CachedPostgresStorage {
     /// In-memory cache of routing rules
     rules_cache: Arc<RwLock<Vec<TopicRoutingRule>>>,
 
+    /// Bumped whenever a refresh changes the cached rules
+    rules_revision: Arc<AtomicU64>,
+
     /// In-memory cache of topic validations
     validations_cache: Arc<RwLock<HashMap<String, Vec<TopicValidationConfig>>>>,
 
@@ -47,6 +51,7 @@ impl CachedPostgresStorage {
         let storage = CachedPostgresStorage {
             postgres: Arc::new(postgres),
             rules_cache,
+            rules_revision: Arc::new(AtomicU64::new(0)),
             validations_cache,
             last_refresh,
             refresh_interval: Duration::from_secs(refresh_interval_secs),
@@ -70,6 +75,7 @@ impl CachedPostgresStorage {
         CachedPostgresStorage {
             postgres: Arc::clone(&self.postgres),
             rules_cache: Arc::clone(&self.rules_cache),
+            rules_revision: Arc::clone(&self.rules_revision),
             validations_cache: Arc::clone(&self.validations_cache),
             last_refresh: Arc::clone(&self.last_refresh),
             refresh_interval: self.refresh_interval,
@@ -122,7 +128,10 @@ impl CachedPostgresStorage {
         // Update the caches
         {
             let mut rules_cache = self.rules_cache.write().unwrap();
-            *rules_cache = rules;
+            if *rules_cache != rules {
+                *rules_cache = rules;
+                self.rules_revision.fetch_add(1, Ordering::Release);
+            }
         }
 
         {
@@ -201,6 +210,10 @@ impl Storage for CachedPostgresStorage {
         Ok(rules.clone())
     }
 
+    fn rules_revision(&self) -> Option<u64> {
+        Some(self.rules_revision.load(Ordering::Acquire))
+    }
+
     async fn update_rule(&self, id: Uuid, rule: &TopicRoutingRule) -> Result<(), StorageError> {
         // Update in database
         self.postgres.update_rule(id, rule).await?;
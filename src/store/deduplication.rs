@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::gateway::gateway::TopicOutcome;
+use crate::store::storage::StorageError;
+
+/// State of an idempotency key after [`DeduplicationStore::claim`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Claim {
+    /// The key was free and now belongs to the caller.
+    Claimed,
+    /// Another request holds the key and has not completed yet.
+    InFlight,
+    /// The key was already handled, with these outcomes.
+    Completed(Vec<TopicOutcome>),
+}
+
+/// Idempotency keys of recently handled events.
+#[async_trait]
+pub trait DeduplicationStore: Send + Sync {
+    /// Claims `key` for `lease`, unless it is held by another request or
+    /// completed within the deduplication window.
+    async fn claim(&self, key: &str, lease: Duration) -> Result<Claim, StorageError>;
+    /// Records the outcomes of a claimed key and keeps them for `ttl`.
+    async fn complete(
+        &self,
+        key: &str,
+        outcomes: &[TopicOutcome],
+        ttl: Duration,
+    ) -> Result<(), StorageError>;
+    /// Frees a claimed key that did not complete, so that it can be retried.
+    async fn release(&self, key: &str) -> Result<(), StorageError>;
+}
+
+struct Entry {
+    expires_at: Instant,
+    outcomes: Option<Vec<TopicOutcome>>,
+}
+
+#[derive(Default)]
+struct Keys {
+    entries: HashMap<String, Entry>,
+    /// Keys in the order they were claimed or completed, with the expiry they
+    /// had then; stale positions are skipped when purging. Leases and the
+    /// window differ, so an entry may expire before it reaches the front.
+    expiries: VecDeque<(Instant, String)>,
+}
+
+impl Keys {
+    fn purge(&mut self, now: Instant) {
+        while let Some((expires_at, _)) = self.expiries.front() {
+            if *expires_at > now {
+                break;
+            }
+            let (expires_at, key) = self.expiries.pop_front().unwrap();
+            if self
+                .entries
+                .get(&key)
+                .is_some_and(|entry| entry.expires_at == expires_at)
+            {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    fn insert(&mut self, key: &str, expires_at: Instant, outcomes: Option<Vec<TopicOutcome>>) {
+        self.entries.insert(
+            key.to_string(),
+            Entry {
+                expires_at,
+                outcomes,
+            },
+        );
+        self.expiries.push_back((expires_at, key.to_string()));
+    }
+}
+
+/// Deduplication window of a single gateway instance.
+#[derive(Default)]
+pub struct InMemoryDeduplicationStore {
+    keys: Mutex<Keys>,
+}
+
+impl InMemoryDeduplicationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl DeduplicationStore for InMemoryDeduplicationStore {
+    async fn claim(&self, key: &str, lease: Duration) -> Result<Claim, StorageError> {
+        let now = Instant::now();
+        let mut keys = self.keys.lock().unwrap();
+        keys.purge(now);
+        if let Some(entry) = keys.entries.get(key).filter(|entry| entry.expires_at > now) {
+            return Ok(match &entry.outcomes {
+                Some(outcomes) => Claim::Completed(outcomes.clone()),
+                None => Claim::InFlight,
+            });
+        }
+        keys.insert(key, now + lease, None);
+        Ok(Claim::Claimed)
+    }
+
+    async fn complete(
+        &self,
+        key: &str,
+        outcomes: &[TopicOutcome],
+        ttl: Duration,
+    ) -> Result<(), StorageError> {
+        let mut keys = self.keys.lock().unwrap();
+        keys.insert(key, Instant::now() + ttl, Some(outcomes.to_vec()));
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<(), StorageError> {
+        let mut keys = self.keys.lock().unwrap();
+        if keys
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.outcomes.is_none())
+        {
+            keys.entries.remove(key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::gateway::EventStatus;
+
+    #[tokio::test]
+    async fn claims_keys_once_per_window() {
+        let store = InMemoryDeduplicationStore::new();
+        let lease = Duration::from_secs(60);
+        let outcomes = vec![TopicOutcome {
+            topic: "orders".to_string(),
+            status: EventStatus::Published,
+        }];
+
+        assert_eq!(store.claim("a", lease).await.unwrap(), Claim::Claimed);
+        assert_eq!(store.claim("a", lease).await.unwrap(), Claim::InFlight);
+        store.release("a").await.unwrap();
+        assert_eq!(store.claim("a", lease).await.unwrap(), Claim::Claimed);
+
+        store.complete("a", &outcomes, lease).await.unwrap();
+        store.release("a").await.unwrap();
+        assert_eq!(
+            store.claim("a", lease).await.unwrap(),
+            Claim::Completed(outcomes.clone())
+        );
+
+        store
+            .complete("a", &outcomes, Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(store.claim("a", lease).await.unwrap(), Claim::Claimed);
+    }
+}
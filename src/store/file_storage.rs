@@ -6,16 +6,21 @@ use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
 
+/// Rules are only expected to change through this storage; files edited by
+/// other processes are picked up after a restart.
 pub struct FileStorage {
     base_path: PathBuf,
+    rules_revision: AtomicU64,
 }
 
 impl FileStorage {
     pub fn new<P: AsRef<Path>>(base_path: P) -> Self {
         FileStorage {
             base_path: base_path.as_ref().to_path_buf(),
+            rules_revision: AtomicU64::new(0),
         }
     }
 
@@ -50,6 +55,7 @@ impl Storage for FileStorage {
         let path = self.get_rule_path(rule.id);
         let json = serde_json::to_string_pretty(rule)?;
         fs::write(path, json)?;
+        self.rules_revision.fetch_add(1, Ordering::Release);
         Ok(())
     }
 
@@ -79,6 +85,7 @@ impl Storage for FileStorage {
         }
         let json = serde_json::to_string_pretty(rule)?;
         fs::write(path, json)?;
+        self.rules_revision.fetch_add(1, Ordering::Release);
         Ok(())
     }
 
@@ -88,9 +95,14 @@ impl Storage for FileStorage {
             return Err(StorageError::NotFound);
         }
         fs::remove_file(path)?;
+        self.rules_revision.fetch_add(1, Ordering::Release);
         Ok(())
     }
 
+    fn rules_revision(&self) -> Option<u64> {
+        Some(self.rules_revision.load(Ordering::Acquire))
+    }
+
     async fn add_topic_validation(&self, v: &TopicValidationConfig) -> Result<(), StorageError> {
         self.ensure_dir(&self.get_validations_path())?;
         let path = self.get_validation_path(v.id);
@@ -136,7 +148,7 @@ impl Storage for FileStorage {
 mod tests {
     use super::*;
     use crate::model::expressions::{Condition, StringExpression};
-    use crate::model::topic::Topic;
+    use crate::model::topic::TopicTemplate;
     use tempfile::TempDir;
     use uuid::Uuid;
 
@@ -144,9 +156,18 @@ mod tests {
         TopicRoutingRule {
             id: Uuid::new_v4(),
             order: 0,
-            topic: Topic::new("test_topic").unwrap(),
+            topic: TopicTemplate::new("test_topic").unwrap(),
             description: None,
             group_metadata_field: None,
+            terminal: true,
+            publisher: None,
+            format: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            mqtt_topic_mapping: None,
+            stream_max_length: None,
+            payload_conditions: Vec::new(),
+            field_conditions: Default::default(),
             event_version_condition: None,
             event_type_condition: Condition::ONE(StringExpression::Equals {
                 value: "test_event".to_string(),
@@ -190,7 +211,7 @@ mod tests {
         let storage = FileStorage::new(temp_dir.path());
         let mut rule = create_dummy_rule();
         storage.add_rule(&rule).await?;
-        rule.topic = Topic::new("updated_topic").unwrap();
+        rule.topic = TopicTemplate::new("updated_topic").unwrap();
         storage.update_rule(rule.id, &rule).await?;
         let retrieved = storage
             .get_all_rules()
@@ -198,7 +219,10 @@ mod tests {
             .into_iter()
             .find(|item| item.id == rule.id)
             .unwrap();
-        assert_eq!(retrieved.topic, Topic::new("updated_topic").unwrap());
+        assert_eq!(
+            retrieved.topic,
+            TopicTemplate::new("updated_topic").unwrap()
+        );
         Ok(())
     }
 
@@ -216,4 +240,26 @@ mod tests {
             .all(|item| item.id != rule.id));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_file_storage_persists_field_conditions() -> Result<(), StorageError> {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        let mut rule = create_dummy_rule();
+        rule.field_conditions.insert(
+            "transportMetadata.jwt_sub".parse().unwrap(),
+            Condition::ONE(StringExpression::Equals {
+                value: "tenant-a".to_string(),
+            }),
+        );
+        storage.add_rule(&rule).await?;
+        let retrieved = storage
+            .get_all_rules()
+            .await?
+            .into_iter()
+            .find(|item| item.id == rule.id)
+            .unwrap();
+        assert_eq!(retrieved.field_conditions, rule.field_conditions);
+        Ok(())
+    }
 }
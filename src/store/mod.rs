@@ -1,5 +1,7 @@
 pub mod cached_postgres_storage;
+pub mod deduplication;
 pub mod file_storage;
 pub mod migrations;
+pub mod postgres_deduplication;
 pub mod postgres_storage;
 pub mod storage;
@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use log::{debug, warn};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time;
+
+use crate::configuration::PostgresDatabaseConfig;
+use crate::gateway::gateway::TopicOutcome;
+use crate::store::deduplication::{Claim, DeduplicationStore};
+use crate::store::postgres_storage::create_pool;
+use crate::store::storage::StorageError;
+
+/// How often expired idempotency keys are deleted.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Deduplication window shared by every gateway replica through the
+/// `idempotency_keys` table. Expects the schema migrations to have run.
+pub struct PostgresDeduplicationStore {
+    pool: Pool,
+}
+
+impl PostgresDeduplicationStore {
+    pub fn new(config: &PostgresDatabaseConfig) -> Result<Self, StorageError> {
+        let pool = create_pool(config)?;
+
+        let purge_pool = pool.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match purge(&purge_pool).await {
+                    Ok(deleted) => debug!("Purged {deleted} expired idempotency keys"),
+                    Err(e) => warn!("Failed to purge idempotency keys: {e}"),
+                }
+            }
+        });
+
+        Ok(PostgresDeduplicationStore { pool })
+    }
+}
+
+async fn purge(pool: &Pool) -> Result<u64, StorageError> {
+    let client = pool.get().await?;
+    Ok(client
+        .execute("DELETE FROM idempotency_keys WHERE expires_at < now()", &[])
+        .await?)
+}
+
+#[async_trait]
+impl DeduplicationStore for PostgresDeduplicationStore {
+    async fn claim(&self, key: &str, lease: Duration) -> Result<Claim, StorageError> {
+        let client = self.pool.get().await?;
+
+        // Takes over a free or expired key; a live key is left untouched.
+        let stmt = client
+            .prepare_cached(
+                "INSERT INTO idempotency_keys (key, outcomes, expires_at)
+                 VALUES ($1, NULL, now() + make_interval(secs => $2))
+                 ON CONFLICT (key) DO UPDATE
+                 SET outcomes = NULL, expires_at = EXCLUDED.expires_at
+                 WHERE idempotency_keys.expires_at < now()
+                 RETURNING key",
+            )
+            .await?;
+        if client
+            .query_opt(&stmt, &[&key, &lease.as_secs_f64()])
+            .await?
+            .is_some()
+        {
+            return Ok(Claim::Claimed);
+        }
+
+        let row = client
+            .query_opt(
+                "SELECT outcomes FROM idempotency_keys WHERE key = $1",
+                &[&key],
+            )
+            .await?;
+        let outcomes: Option<Value> = row.and_then(|row| row.get("outcomes"));
+        Ok(match outcomes {
+            Some(outcomes) => Claim::Completed(serde_json::from_value(outcomes)?),
+            None => Claim::InFlight,
+        })
+    }
+
+    async fn complete(
+        &self,
+        key: &str,
+        outcomes: &[TopicOutcome],
+        ttl: Duration,
+    ) -> Result<(), StorageError> {
+        let client = self.pool.get().await?;
+        client
+            .execute(
+                "UPDATE idempotency_keys
+                 SET outcomes = $2, expires_at = now() + make_interval(secs => $3)
+                 WHERE key = $1",
+                &[&key, &serde_json::to_value(outcomes)?, &ttl.as_secs_f64()],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<(), StorageError> {
+        let client = self.pool.get().await?;
+        client
+            .execute(
+                "DELETE FROM idempotency_keys WHERE key = $1 AND outcomes IS NULL",
+                &[&key],
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
 use crate::model::routing::{DataSchema, TopicRoutingRule, TopicValidationConfig};
-use crate::model::topic::Topic;
+use crate::model::topic::{Topic, TopicTemplate};
 use crate::store::storage::{Storage, StorageError};
 use async_trait::async_trait;
 use deadpool_postgres::{Config, Pool, Runtime};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::num::NonZeroU64;
 use tokio_postgres::NoTls;
 use uuid::Uuid;
 
@@ -21,22 +22,50 @@ impl PostgresStorage {
             .await
             .map_err(|e| StorageError::IoError(std::io::Error::other(e)))?;
 
-        let mut cfg = Config::new();
+        Ok(PostgresStorage {
+            pool: create_pool(config)?,
+        })
+    }
+}
 
-        // Parse endpoint which contains both host and port
-        let (host, port) = parse_endpoint(&config.endpoint);
-        cfg.host = Some(host);
-        cfg.port = Some(port);
-        cfg.user = Some(config.username.clone());
-        cfg.password = Some(config.password.clone());
-        cfg.dbname = Some(config.dbname.clone());
+/// Connection pool for the `database` settings.
+pub(crate) fn create_pool(
+    config: &crate::configuration::PostgresDatabaseConfig,
+) -> Result<Pool, StorageError> {
+    let mut cfg = Config::new();
+
+    // Parse endpoint which contains both host and port
+    let (host, port) = parse_endpoint(&config.endpoint);
+    cfg.host = Some(host);
+    cfg.port = Some(port);
+    cfg.user = Some(config.username.clone());
+    cfg.password = Some(config.password.clone());
+    cfg.dbname = Some(config.dbname.clone());
+
+    cfg.create_pool(Some(Runtime::Tokio1), NoTls)
+        .map_err(|e| StorageError::IoError(std::io::Error::other(e)))
+}
 
-        let pool = cfg
-            .create_pool(Some(Runtime::Tokio1), NoTls)
-            .map_err(|e| StorageError::IoError(std::io::Error::other(e)))?;
+/// `stream_max_length` as stored in its BIGINT column.
+fn stream_max_length_to_sql(length: Option<NonZeroU64>) -> Result<Option<i64>, StorageError> {
+    length
+        .map(|length| {
+            i64::try_from(length.get()).map_err(|_| {
+                StorageError::Other(format!("stream_max_length {length} is too large"))
+            })
+        })
+        .transpose()
+}
 
-        Ok(PostgresStorage { pool })
-    }
+fn stream_max_length_from_sql(length: Option<i64>) -> Result<Option<NonZeroU64>, StorageError> {
+    length
+        .map(|length| {
+            u64::try_from(length)
+                .ok()
+                .and_then(NonZeroU64::new)
+                .ok_or_else(|| StorageError::Other(format!("invalid stream_max_length {length}")))
+        })
+        .transpose()
 }
 
 // Helper function to parse endpoint into host and port
@@ -63,8 +92,8 @@ impl Storage for PostgresStorage {
             .map_err(|e| StorageError::IoError(std::io::Error::other(e)))?;
 
         let stmt = client.prepare_cached(
-            "INSERT INTO routing_rules (id, order_num, topic, description, group_metadata_field, event_version_condition, event_type_condition)
-             VALUES ($1, $2, $3, $4, $5, $6, $7)"
+            "INSERT INTO routing_rules (id, order_num, topic, description, group_metadata_field, event_version_condition, event_type_condition, terminal, field_conditions, payload_conditions, publisher, format, partition_key, partition_metadata_field, mqtt_topic_mapping, stream_max_length)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)"
         ).await?;
 
         client
@@ -78,6 +107,15 @@ impl Storage for PostgresStorage {
                     &rule.group_metadata_field,
                     &serde_json::to_value(&rule.event_version_condition)?,
                     &serde_json::to_value(&rule.event_type_condition)?,
+                    &rule.terminal,
+                    &serde_json::to_value(&rule.field_conditions)?,
+                    &serde_json::to_value(&rule.payload_conditions)?,
+                    &rule.publisher,
+                    &serde_json::to_value(rule.format)?,
+                    &serde_json::to_value(&rule.partition_key)?,
+                    &rule.partition_metadata_field,
+                    &serde_json::to_value(&rule.mqtt_topic_mapping)?,
+                    &stream_max_length_to_sql(rule.stream_max_length)?,
                 ],
             )
             .await?;
@@ -93,7 +131,7 @@ impl Storage for PostgresStorage {
             .map_err(|e| StorageError::IoError(std::io::Error::other(e)))?;
 
         let stmt = client.prepare_cached(
-            "SELECT id, order_num, topic, description, group_metadata_field, event_version_condition, event_type_condition
+            "SELECT id, order_num, topic, description, group_metadata_field, event_version_condition, event_type_condition, terminal, field_conditions, payload_conditions, publisher, format, partition_key, partition_metadata_field, mqtt_topic_mapping, stream_max_length
              FROM routing_rules ORDER BY order_num"
         ).await?;
 
@@ -103,16 +141,35 @@ impl Storage for PostgresStorage {
         for row in rows {
             let event_version_condition: Value = row.get("event_version_condition");
             let event_type_condition: Value = row.get("event_type_condition");
+            let field_conditions: Value = row.get("field_conditions");
+            let payload_conditions: Value = row.get("payload_conditions");
 
             rules.push(TopicRoutingRule {
                 id: row.get("id"),
                 order: row.get("order_num"),
-                topic: Topic::new(row.get::<_, String>("topic"))
+                topic: TopicTemplate::new(row.get::<_, String>("topic"))
                     .map_err(|e| StorageError::Other(e.to_string()))?,
                 description: row.get("description"),
                 group_metadata_field: row.get("group_metadata_field"),
                 event_version_condition: serde_json::from_value(event_version_condition)?,
                 event_type_condition: serde_json::from_value(event_type_condition)?,
+                terminal: row.get("terminal"),
+                field_conditions: serde_json::from_value(field_conditions)?,
+                payload_conditions: serde_json::from_value(payload_conditions)?,
+                publisher: row.get("publisher"),
+                format: serde_json::from_value(
+                    row.get::<_, Option<Value>>("format").unwrap_or(Value::Null),
+                )?,
+                partition_key: serde_json::from_value(
+                    row.get::<_, Option<Value>>("partition_key")
+                        .unwrap_or(Value::Null),
+                )?,
+                partition_metadata_field: row.get("partition_metadata_field"),
+                mqtt_topic_mapping: serde_json::from_value(
+                    row.get::<_, Option<Value>>("mqtt_topic_mapping")
+                        .unwrap_or(Value::Null),
+                )?,
+                stream_max_length: stream_max_length_from_sql(row.get("stream_max_length"))?,
             });
         }
 
@@ -132,7 +189,10 @@ impl Storage for PostgresStorage {
              SET order_num = $2, topic = $3, description = $4,
                  group_metadata_field = $5,
                  event_version_condition = $6, event_type_condition = $7,
-                 updated_at = NOW()
+                 terminal = $8, field_conditions = $9,
+                 payload_conditions = $10, publisher = $11, format = $12,
+                 partition_key = $13, partition_metadata_field = $14,
+                 mqtt_topic_mapping = $15, stream_max_length = $16, updated_at = NOW()
              WHERE id = $1",
             )
             .await?;
@@ -148,6 +208,15 @@ impl Storage for PostgresStorage {
                     &rule.group_metadata_field,
                     &serde_json::to_value(&rule.event_version_condition)?,
                     &serde_json::to_value(&rule.event_type_condition)?,
+                    &rule.terminal,
+                    &serde_json::to_value(&rule.field_conditions)?,
+                    &serde_json::to_value(&rule.payload_conditions)?,
+                    &rule.publisher,
+                    &serde_json::to_value(rule.format)?,
+                    &serde_json::to_value(&rule.partition_key)?,
+                    &rule.partition_metadata_field,
+                    &serde_json::to_value(&rule.mqtt_topic_mapping)?,
+                    &stream_max_length_to_sql(rule.stream_max_length)?,
                 ],
             )
             .await?;
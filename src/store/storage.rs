@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use uuid::Uuid;
 
@@ -77,6 +78,13 @@ pub trait Storage: Send + Sync {
     async fn update_rule(&self, id: Uuid, rule: &TopicRoutingRule) -> Result<(), StorageError>;
     async fn delete_rule(&self, id: Uuid) -> Result<(), StorageError>;
 
+    /// Counter that changes whenever the routing rules change, so callers
+    /// can cache what they derive from them. `None` means the storage cannot
+    /// tell and rules must be reloaded for every use.
+    fn rules_revision(&self) -> Option<u64> {
+        None
+    }
+
     async fn add_topic_validation(&self, v: &TopicValidationConfig) -> Result<(), StorageError>;
     async fn get_all_topic_validations(
         &self,
@@ -100,6 +108,8 @@ pub trait Storage: Send + Sync {
 pub struct InMemoryStorage {
     routing_rules: RwLock<Vec<TopicRoutingRule>>,
     topic_validations: RwLock<HashMap<String, Vec<TopicValidationConfig>>>,
+    #[serde(skip)]
+    rules_revision: AtomicU64,
 }
 
 impl InMemoryStorage {
@@ -107,6 +117,7 @@ impl InMemoryStorage {
         InMemoryStorage {
             routing_rules: RwLock::new(Vec::new()),
             topic_validations: RwLock::new(HashMap::new()),
+            rules_revision: AtomicU64::new(0),
         }
     }
 
@@ -130,6 +141,7 @@ impl Storage for InMemoryStorage {
         }
         rules.push(rule.clone());
         rules.sort_by_key(|item| (item.order, item.id));
+        self.rules_revision.fetch_add(1, Ordering::Release);
         Ok(())
     }
 
@@ -154,6 +166,7 @@ impl Storage for InMemoryStorage {
         updated.id = id;
         *existing = updated;
         rules.sort_by_key(|item| (item.order, item.id));
+        self.rules_revision.fetch_add(1, Ordering::Release);
         Ok(())
     }
 
@@ -164,9 +177,15 @@ impl Storage for InMemoryStorage {
             .map_err(|_| Self::lock_error("routing rules"))?;
         let original_len = rules.len();
         rules.retain(|rule| rule.id != id);
-        (rules.len() != original_len)
-            .then_some(())
-            .ok_or(StorageError::NotFound)
+        if rules.len() == original_len {
+            return Err(StorageError::NotFound);
+        }
+        self.rules_revision.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    fn rules_revision(&self) -> Option<u64> {
+        Some(self.rules_revision.load(Ordering::Acquire))
     }
 
     async fn add_topic_validation(&self, v: &TopicValidationConfig) -> Result<(), StorageError> {
@@ -220,7 +239,7 @@ impl Storage for InMemoryStorage {
 #[cfg(test)]
 mod tests {
     use crate::model::expressions::{Condition, StringExpression};
-    use crate::model::topic::Topic;
+    use crate::model::topic::TopicTemplate;
 
     use super::*;
 
@@ -228,9 +247,18 @@ mod tests {
         TopicRoutingRule {
             id: Uuid::new_v4(),
             order,
-            topic: Topic::new("topic").unwrap(),
+            topic: TopicTemplate::new("topic").unwrap(),
             description: None,
             group_metadata_field: None,
+            terminal: true,
+            publisher: None,
+            format: None,
+            partition_key: None,
+            partition_metadata_field: None,
+            mqtt_topic_mapping: None,
+            stream_max_length: None,
+            payload_conditions: Vec::new(),
+            field_conditions: Default::default(),
             event_version_condition: Some(Condition::ONE(StringExpression::Equals {
                 value: "1.0".to_string(),
             })),
@@ -246,8 +274,10 @@ mod tests {
         let first = rule(2);
         let second = rule(1);
 
+        assert_eq!(storage.rules_revision(), Some(0));
         storage.add_rule(&first).await.unwrap();
         storage.add_rule(&second).await.unwrap();
+        assert_eq!(storage.rules_revision(), Some(2));
         assert_eq!(
             storage
                 .get_all_rules()
@@ -273,6 +303,7 @@ mod tests {
         );
 
         storage.delete_rule(first.id).await.unwrap();
+        assert_eq!(storage.rules_revision(), Some(4));
         assert!(storage
             .get_all_rules()
             .await
@@ -16,6 +16,8 @@ const expressionTypes = [
   { value: 'startsWith', label: 'Starts With' },
   { value: 'endsWith', label: 'Ends With' },
   { value: 'contains', label: 'Contains' },
+  { value: 'semverRange', label: 'Semver Range' },
+  { value: 'semverMajor', label: 'Semver Major' },
 ];
 
 const operatorTypes = [
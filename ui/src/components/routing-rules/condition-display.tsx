@@ -20,6 +20,8 @@ const expressionColors = {
   startsWith: 'bg-yellow-100 text-yellow-800',
   endsWith: 'bg-orange-100 text-orange-800',
   contains: 'bg-pink-100 text-pink-800',
+  semverRange: 'bg-blue-100 text-blue-800',
+  semverMajor: 'bg-blue-100 text-blue-800',
 } as const;
 
 function formatExpressionType(type: string): string {
@@ -27,6 +29,8 @@ function formatExpressionType(type: string): string {
     case 'regexMatch': return 'regex';
     case 'startsWith': return 'starts with';
     case 'endsWith': return 'ends with';
+    case 'semverRange': return 'semver';
+    case 'semverMajor': return 'major version';
     default: return type;
   }
 }
@@ -25,6 +25,8 @@ export function ConditionTextDisplay({ condition }: ConditionTextDisplayProps) {
     startsWith: 'bg-yellow-100 text-yellow-800',
     endsWith: 'bg-orange-100 text-orange-800',
     contains: 'bg-pink-100 text-pink-800',
+    semverRange: 'bg-blue-100 text-blue-800',
+    semverMajor: 'bg-blue-100 text-blue-800',
   } as const;
 
   // Generate a text summary of the condition
@@ -108,6 +110,8 @@ export function ConditionTextDisplay({ condition }: ConditionTextDisplayProps) {
       case 'regexMatch': return 'regex';
       case 'startsWith': return 'starts with';
       case 'endsWith': return 'ends with';
+      case 'semverRange': return 'semver';
+      case 'semverMajor': return 'major version';
       default: return type;
     }
   };
@@ -1,8 +1,8 @@
 import React, { useState } from 'react';
 import { Form, Input, Select, Button, Tabs, Table, Tag, Tooltip } from 'antd';
 import { v4 as uuidv4 } from 'uuid';
-import { Event, EventFormValues, JsonValue } from '../types/events';
-import { sendEvent } from '../services/events';
+import { Event, EventExplanation, EventFormValues, JsonValue } from '../types/events';
+import { explainEvent, sendEvent } from '../services/events';
 import { useToast } from "@/hooks/use-toast"
 import { Copy, Trash2 } from 'lucide-react';
 
@@ -21,11 +21,45 @@ interface StoredEvent {
 
 const HISTORY_STORAGE_KEY = 'event_gateway_history';
 
+const ExplanationView: React.FC<{ explanation: EventExplanation }> = ({ explanation }) => (
+  <div style={{ marginTop: 24, maxWidth: 600 }}>
+    <p>
+      Result: <Tag>{explanation.status}</Tag>
+      {explanation.deadLetter && <span>dead-lettered to {explanation.deadLetter}</span>}
+    </p>
+    {explanation.rules.map((rule) => (
+      <div key={rule.id}>
+        <Tag color={rule.matched ? 'green' : 'default'}>{rule.matched ? 'matched' : 'skipped'}</Tag>
+        {rule.topic}
+        {!rule.matched && <span> (failed: {rule.failedConditions.join(', ')})</span>}
+      </div>
+    ))}
+    {explanation.destinations.map((destination) => (
+      <div key={destination.ruleId} style={{ marginTop: 8 }}>
+        <strong>{destination.topic}</strong> <Tag>{destination.status}</Tag>
+        {destination.error && <span>{destination.error}</span>}
+        {destination.schemas.map((schema) => (
+          <div key={schema.name} style={{ marginLeft: 16 }}>
+            <Tag color={schema.status === 'invalid' ? 'red' : 'green'}>{schema.status}</Tag>
+            {schema.name}
+            {schema.errors.map((error) => (
+              <div key={error.schema_path + error.instance_path} style={{ marginLeft: 16 }}>
+                {error.instance_path || '/'}: {error.message}
+              </div>
+            ))}
+          </div>
+        ))}
+      </div>
+    ))}
+  </div>
+);
+
 const PlaygroundPage: React.FC = () => {
   const { toast } = useToast();
   const [form] = Form.useForm();
   const [rawJson, setRawJson] = useState('');
   const [loading, setLoading] = useState(false);
+  const [explanation, setExplanation] = useState<EventExplanation | null>(null);
   const [history, setHistory] = useState<StoredEvent[]>(() => {
     const savedHistory = localStorage.getItem(HISTORY_STORAGE_KEY);
     if (savedHistory) {
@@ -71,20 +105,37 @@ const PlaygroundPage: React.FC = () => {
     localStorage.setItem(HISTORY_STORAGE_KEY, JSON.stringify(newHistory));
   };
 
-  const handleSubmit = async (values: EventFormValues) => {
+  const toEvent = (values: EventFormValues): Event => ({
+    id: uuidv4(),
+    eventType: values.type,
+    eventVersion: values.version || 'N/A',
+    data: values.dataType === 'json' ? JSON.parse(values.data) : values.data,
+    metadata: values.metadata ? JSON.parse(values.metadata) : undefined,
+    transportMetadata: values.transportMetadata ? JSON.parse(values.transportMetadata) : undefined,
+    timestamp: new Date().toISOString(),
+    origin: 'playground',
+  });
+
+  const handleExplain = async (buildEvent: () => Event) => {
     setLoading(true);
     try {
-      const event: Event = {
-        id: uuidv4(),
-        eventType: values.type,
-        eventVersion: values.version || 'N/A',
-        data: values.dataType === 'json' ? JSON.parse(values.data) : values.data,
-        metadata: values.metadata ? JSON.parse(values.metadata) : undefined,
-        transportMetadata: values.transportMetadata ? JSON.parse(values.transportMetadata) : undefined,
-        timestamp: new Date().toISOString(),
-        origin: 'playground',
-      };
+      setExplanation(await explainEvent(buildEvent()));
+    } catch (error) {
+      setExplanation(null);
+      toast({
+        title: "Error",
+        description: error instanceof Error ? error.message : 'Failed to explain event',
+        variant: "destructive",
+      });
+    } finally {
+      setLoading(false);
+    }
+  };
 
+  const handleSubmit = async (values: EventFormValues) => {
+    setLoading(true);
+    try {
+      const event = toEvent(values);
       await sendEvent(event);
       toast({
         title: "Success",
@@ -277,8 +328,19 @@ const PlaygroundPage: React.FC = () => {
               >
                 Send Event
               </Button>
+              <Button
+                loading={loading}
+                onClick={async () => {
+                  const values = await form.validateFields();
+                  await handleExplain(() => toEvent(values));
+                }}
+                style={{ marginLeft: 8, width: '120px' }}
+              >
+                Explain
+              </Button>
             </Form.Item>
           </Form>
+          {explanation && <ExplanationView explanation={explanation} />}
         </TabPane>
 
         <TabPane tab="Raw JSON" key="raw">
@@ -320,6 +382,14 @@ const PlaygroundPage: React.FC = () => {
             >
               Send Event
             </Button>
+            <Button
+              onClick={() => handleExplain(() => JSON.parse(rawJson))}
+              loading={loading}
+              style={{ marginTop: 16, marginLeft: 8, width: '120px' }}
+            >
+              Explain
+            </Button>
+            {explanation && <ExplanationView explanation={explanation} />}
           </div>
         </TabPane>
 
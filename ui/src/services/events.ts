@@ -1,9 +1,15 @@
 import { fetchApi } from './api';
-import { Event } from '../types/events';
+import { Event, EventExplanation } from '../types/events';
 
 export async function sendEvent(event: Event): Promise<void> {
   await fetchApi('/event', {
     method: 'POST',
     body: JSON.stringify(event),
   });
-} 
\ No newline at end of file
+} 
+export async function explainEvent(event: Event): Promise<EventExplanation> {
+  return fetchApi<EventExplanation>('/event/explain', {
+    method: 'POST',
+    body: JSON.stringify(event),
+  });
+}
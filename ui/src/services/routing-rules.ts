@@ -1,7 +1,14 @@
 import { Uuid } from '../types/common';
 
 export interface StringExpression {
-  type: 'regexMatch' | 'equals' | 'startsWith' | 'endsWith' | 'contains';
+  type:
+    | 'regexMatch'
+    | 'equals'
+    | 'startsWith'
+    | 'endsWith'
+    | 'contains'
+    | 'semverRange'
+    | 'semverMajor';
   value: string;
 }
 
@@ -13,14 +20,46 @@ export interface Condition {
   value?: string;
 }
 
+export type OutputFormat =
+  | 'gateway'
+  | 'cloudevents-structured'
+  | 'cloudevents-binary'
+  | 'data-only';
+
+export type PartitionKey =
+  | { type: 'eventId' }
+  | { type: 'metadata'; field: string }
+  | { type: 'payload'; pointer: string }
+  | { type: 'composite'; fields: string[]; separator?: string }
+  | { type: 'null' };
+
+export interface MqttTopicMapping {
+  dotsToSlashes?: boolean;
+  prefix?: string | null;
+}
+
 export interface TopicRoutingRule {
   id: Uuid;
   order: number;
   topic: string;
   description?: string;
   groupMetadataField?: string;
+  terminal?: boolean;
+  publisher?: string | null;
+  format?: OutputFormat | null;
+  partitionKey?: PartitionKey | null;
+  partitionMetadataField?: string | null;
+  mqttTopicMapping?: MqttTopicMapping | null;
+  streamMaxLength?: number | null;
   eventVersionCondition?: Condition;
   eventTypeCondition: Condition;
+  fieldConditions?: Record<string, Condition>;
+  payloadConditions?: PayloadCondition[];
+}
+
+export interface PayloadCondition {
+  pointer: string;
+  condition: Condition | { type: string; value: number | boolean };
 }
 
 const API_BASE = '/api/v1';
@@ -28,3 +28,42 @@ export interface EventFormValues {
   metadata?: string;
   transportMetadata?: string;
 }
+
+export type EventStatus =
+  | 'published'
+  | 'dead_lettered'
+  | 'schema_invalid'
+  | 'no_route'
+  | 'topic_render_failed'
+  | 'internal_error';
+
+export interface SchemaExplanation {
+  name: string;
+  status: 'valid' | 'invalid' | 'skipped';
+  errors: { instance_path: string; schema_path: string; message: string }[];
+}
+
+export interface DestinationExplanation {
+  ruleId: string;
+  topic: string;
+  publisher: string | null;
+  status: EventStatus;
+  error: string | null;
+  schemas: SchemaExplanation[];
+}
+
+export interface RuleExplanation {
+  id: string;
+  order: number;
+  topic: string;
+  matched: boolean;
+  failedConditions: string[];
+}
+
+export interface EventExplanation {
+  eventId: string;
+  status: EventStatus;
+  rules: RuleExplanation[];
+  destinations: DestinationExplanation[];
+  deadLetter: string | null;
+}